@@ -1,49 +1,168 @@
 use num_traits::sign::signum;
-use std::io::{self, Read};
+use std::{
+    env,
+    fmt::Display,
+    io::{self, Read},
+};
 
 /// Day 2: Red-Nosed Reports
 ///
+/// Run with argument `diagnose` to print every report annotated with the
+/// reason it is unsafe, instead of the puzzle answers.
+///
 /// <https://adventofcode.com/2024/day/2>
 fn main() -> io::Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
+    if env::args().nth(1).as_deref() == Some("diagnose") {
+        for levels in parse_reports(&input) {
+            println!("{}", annotate(&levels));
+        }
+        return Ok(());
+    }
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
     Ok(())
 }
 
-fn is_safe(levels: &[i32]) -> bool {
-    let dir = signum(levels[1] - levels[0]);
-    if dir == 0 {
-        return false;
+/// Reason for a report being unsafe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ViolationKind {
+    /// Levels change from increasing to decreasing or vice versa.
+    DirectionChange,
+    /// Two adjacent levels are equal.
+    ZeroStep,
+    /// Adjacent levels differ by more than three.
+    StepTooLarge,
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ViolationKind::DirectionChange => "direction change",
+            ViolationKind::ZeroStep => "zero step",
+            ViolationKind::StepTooLarge => "step too large",
+        };
+        write!(f, "{}", s)
     }
+}
+
+/// First rule violation in a report.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Violation {
+    /// Index of the level, where the violating step ends.
+    index: usize,
+    kind: ViolationKind,
+}
+
+/// Safety of a report, with the Problem Dampener taken into account.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Safety {
+    Safe,
+    /// Unsafe as such, but safe when level at `removed` is dropped.
+    Dampened {
+        violation: Violation,
+        removed: usize,
+    },
+    Unsafe(Violation),
+}
+
+/// Find the first rule violation in the report.
+///
+/// Returns None if report is safe. Reports with fewer than two levels have
+/// no steps and are considered safe.
+fn diagnose(levels: &[i32]) -> Option<Violation> {
+    if levels.len() < 2 {
+        return None;
+    }
+    let dir = signum(levels[1] - levels[0]);
     for i in 1..(levels.len()) {
         let diff = levels[i] - levels[i - 1];
-        if signum(diff) != dir || diff.abs() > 3 {
-            return false;
-        }
+        let kind = if diff == 0 {
+            ViolationKind::ZeroStep
+        } else if signum(diff) != dir {
+            ViolationKind::DirectionChange
+        } else if diff.abs() > 3 {
+            ViolationKind::StepTooLarge
+        } else {
+            continue;
+        };
+        return Some(Violation { index: i, kind });
     }
-    true
+    None
 }
 
-fn is_safe_dampened(levels: &[i32]) -> bool {
-    if is_safe(levels) {
-        return true;
-    }
+/// Diagnose report, trying to remove a single level if it is unsafe.
+fn diagnose_dampened(levels: &[i32]) -> Safety {
+    let violation = match diagnose(levels) {
+        None => return Safety::Safe,
+        Some(v) => v,
+    };
     for i in 0..levels.len() {
         let levels_i: Vec<i32> = levels
             .iter()
             .enumerate()
             .filter_map(|(j, val)| if i == j { None } else { Some(*val) })
             .collect();
-        if is_safe(&levels_i) {
-            return true;
+        if diagnose(&levels_i).is_none() {
+            return Safety::Dampened {
+                violation,
+                removed: i,
+            };
         }
     }
-    false
+    Safety::Unsafe(violation)
 }
 
-fn part1(input: &str) -> u32 {
+fn is_safe(levels: &[i32]) -> bool {
+    diagnose(levels).is_none()
+}
+
+fn is_safe_dampened(levels: &[i32]) -> bool {
+    !matches!(diagnose_dampened(levels), Safety::Unsafe(_))
+}
+
+/// Format report, marking the violating level with `[ ]` and the level
+/// removed by the dampener with `( )`, or `[( )]` if they are the same.
+fn annotate(levels: &[i32]) -> String {
+    let safety = diagnose_dampened(levels);
+    let (mark, removed) = match safety {
+        Safety::Safe => (None, None),
+        Safety::Dampened { violation, removed } => (Some(violation.index), Some(removed)),
+        Safety::Unsafe(violation) => (Some(violation.index), None),
+    };
+    let report = levels
+        .iter()
+        .enumerate()
+        .map(|(i, val)| {
+            let val = if Some(i) == removed {
+                format!("({})", val)
+            } else {
+                val.to_string()
+            };
+            if Some(i) == mark {
+                format!("[{}]", val)
+            } else {
+                val
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    let status = match safety {
+        Safety::Safe => "safe".to_string(),
+        Safety::Dampened { violation, removed } => format!(
+            "{} at level {}, safe by removing level {}",
+            violation.kind, violation.index, removed
+        ),
+        Safety::Unsafe(violation) => {
+            format!("{} at level {}, unsafe", violation.kind, violation.index)
+        }
+    };
+    format!("{}: {}", report, status)
+}
+
+/// Parse reports, one per line. Empty lines are skipped.
+fn parse_reports(input: &str) -> impl Iterator<Item = Vec<i32>> + '_ {
     input
         .lines()
         .map(|line| {
@@ -51,17 +170,16 @@ fn part1(input: &str) -> u32 {
                 .filter_map(|val| val.parse::<i32>().ok())
                 .collect::<Vec<i32>>()
         })
+        .filter(|levels| !levels.is_empty())
+}
+
+fn part1(input: &str) -> u32 {
+    parse_reports(input)
         .filter_map(|levels| if is_safe(&levels) { Some(1) } else { None })
         .count() as u32
 }
 fn part2(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .filter_map(|val| val.parse::<i32>().ok())
-                .collect::<Vec<i32>>()
-        })
+    parse_reports(input)
         .filter_map(|levels| {
             if is_safe_dampened(&levels) {
                 Some(1)
@@ -152,4 +270,71 @@ mod tests {
     fn is_unsafe_dampened() {
         assert!(!is_safe_dampened(&[86, 86, 84, 86, 86]))
     }
+
+    #[test]
+    fn diagnose_direction_change() {
+        let v = diagnose(&[1, 3, 2, 4, 5]).unwrap();
+        assert_eq!(v.index, 2);
+        assert_eq!(v.kind, ViolationKind::DirectionChange);
+    }
+
+    #[test]
+    fn diagnose_zero_step() {
+        let v = diagnose(&[8, 6, 4, 4, 1]).unwrap();
+        assert_eq!(v.index, 3);
+        assert_eq!(v.kind, ViolationKind::ZeroStep);
+    }
+
+    #[test]
+    fn diagnose_zero_first_step() {
+        let v = diagnose(&[86, 86, 84, 86, 86]).unwrap();
+        assert_eq!(v.index, 1);
+        assert_eq!(v.kind, ViolationKind::ZeroStep);
+    }
+
+    #[test]
+    fn diagnose_step_too_large() {
+        let v = diagnose(&[1, 2, 7, 8, 9]).unwrap();
+        assert_eq!(v.index, 2);
+        assert_eq!(v.kind, ViolationKind::StepTooLarge);
+    }
+
+    #[test]
+    fn diagnose_short_reports() {
+        assert_eq!(diagnose(&[]), None);
+        assert_eq!(diagnose(&[5]), None);
+        assert!(is_safe_dampened(&[5]));
+    }
+
+    #[test]
+    fn diagnose_dampened_removed_level() {
+        let safety = diagnose_dampened(&[1, 3, 2, 4, 5]);
+        assert_eq!(
+            safety,
+            Safety::Dampened {
+                violation: Violation {
+                    index: 2,
+                    kind: ViolationKind::DirectionChange
+                },
+                removed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        assert_eq!(annotate(&[7, 6, 4, 2, 1]), "7 6 4 2 1: safe");
+        assert_eq!(
+            annotate(&[1, 2, 7, 8, 9]),
+            "1 2 [7] 8 9: step too large at level 2, unsafe"
+        );
+        assert_eq!(
+            annotate(&[8, 6, 4, 4, 1]),
+            "8 6 (4) [4] 1: zero step at level 3, safe by removing level 2"
+        );
+        assert_eq!(
+            annotate(&[1, 5, 2, 3]),
+            "1 [(5)] 2 3: step too large at level 1, safe by removing level 1"
+        );
+    }
 }
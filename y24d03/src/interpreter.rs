@@ -0,0 +1,460 @@
+use std::{collections::BTreeMap, fmt::Display, ops::Range};
+
+/// Operation callable from the memory dump by name.
+#[derive(Clone, Copy)]
+pub enum Operation {
+    /// Function of fixed arity, evaluating to a value.
+    ///
+    /// `apply` returns None if the result does not fit in `i128`.
+    Function {
+        arity: usize,
+        apply: fn(&[i128]) -> Option<i128>,
+    },
+    /// Sets the enabled flag, given current flag and arguments.
+    Toggle {
+        arity: usize,
+        apply: fn(bool, &[i128]) -> bool,
+    },
+}
+
+impl Operation {
+    fn arity(&self) -> usize {
+        match self {
+            Operation::Function { arity, .. } | Operation::Toggle { arity, .. } => *arity,
+        }
+    }
+}
+
+/// Parsed call, where arguments are literals or nested calls.
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Literal(i128),
+    Call { name: String, args: Vec<Expr> },
+}
+
+/// What happened to a top level call during execution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    /// Function was evaluated and its value added to the accumulator.
+    Executed(i128),
+    /// Function was not evaluated, as execution was disabled.
+    Skipped,
+    /// Toggle was evaluated, and the enabled flag set to the value.
+    Toggled(bool),
+}
+
+/// Trace entry of a top level call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub span: Range<usize>,
+    pub status: Status,
+}
+
+/// Result of a successful run.
+#[derive(Debug, PartialEq)]
+pub struct Execution {
+    pub value: i128,
+    pub trace: Vec<Step>,
+}
+
+/// Value did not fit in the accumulator width.
+#[derive(Debug, PartialEq)]
+pub struct Overflow {
+    /// Span of the top level call being evaluated.
+    pub span: Range<usize>,
+    pub width: u32,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-bit overflow in instruction at {}..{}",
+            self.width, self.span.start, self.span.end
+        )
+    }
+}
+
+/// Deepest nesting of calls, beyond which a call is treated as malformed.
+const MAX_DEPTH: usize = 32;
+
+/// Call found at a position of the memory dump.
+#[derive(Clone, Copy)]
+struct Shape {
+    /// Length in bytes.
+    len: usize,
+    /// Nesting depth, 1 for calls with only literal arguments.
+    height: usize,
+    toggle: bool,
+}
+
+/// Argument of a call, with nested calls referred to by position.
+enum Arg {
+    Literal(i128),
+    Call(usize),
+}
+
+/// Interpreter for the corrupted memory instruction language.
+///
+/// Instructions are calls `name(arg,...)` to operations registered in the
+/// table, where arguments are numbers of 1-3 digits or, for functions,
+/// nested calls up to [`MAX_DEPTH`] levels deep. Values of top level
+/// function calls are summed to a signed accumulator of `width` bits, while
+/// execution is enabled.
+pub struct Interpreter {
+    table: BTreeMap<String, Operation>,
+    width: u32,
+}
+
+impl Interpreter {
+    /// Create interpreter with empty operation table.
+    ///
+    /// * `width` - Accumulator width in bits, 1..=128.
+    pub fn new(width: u32) -> Self {
+        assert!((1..=128).contains(&width), "width must be 1..=128 bits");
+        Interpreter {
+            table: BTreeMap::new(),
+            width,
+        }
+    }
+
+    /// Interpreter with operations of the puzzle: `mul`, `do` and `don't`.
+    pub fn standard(width: u32) -> Self {
+        let mut interpreter = Interpreter::new(width);
+        interpreter
+            .register(
+                "mul",
+                Operation::Function {
+                    arity: 2,
+                    apply: |args| args[0].checked_mul(args[1]),
+                },
+            )
+            .register(
+                "do",
+                Operation::Toggle {
+                    arity: 0,
+                    apply: |_, _| true,
+                },
+            )
+            .register(
+                "don't",
+                Operation::Toggle {
+                    arity: 0,
+                    apply: |_, _| false,
+                },
+            );
+        interpreter
+    }
+
+    /// Standard operations, and additionally `add`, `sub`, and conditional
+    /// toggles `do_if(x)` and `dont_if(x)`, which act when x is non-zero.
+    pub fn extended(width: u32) -> Self {
+        let mut interpreter = Interpreter::standard(width);
+        interpreter
+            .register(
+                "add",
+                Operation::Function {
+                    arity: 2,
+                    apply: |args| args[0].checked_add(args[1]),
+                },
+            )
+            .register(
+                "sub",
+                Operation::Function {
+                    arity: 2,
+                    apply: |args| args[0].checked_sub(args[1]),
+                },
+            )
+            .register(
+                "do_if",
+                Operation::Toggle {
+                    arity: 1,
+                    apply: |enabled, args| enabled || args[0] != 0,
+                },
+            )
+            .register(
+                "dont_if",
+                Operation::Toggle {
+                    arity: 1,
+                    apply: |enabled, args| enabled && args[0] == 0,
+                },
+            );
+        interpreter
+    }
+
+    /// Add operation to the table, replacing any existing one with the name.
+    pub fn register(&mut self, name: &str, operation: Operation) -> &mut Self {
+        self.table.insert(name.to_string(), operation);
+        self
+    }
+
+    /// Execute all instructions in memory dump.
+    pub fn run(&self, input: &str) -> Result<Execution, Overflow> {
+        let bytes = input.as_bytes();
+        let mut value: i128 = 0;
+        let mut enabled = true;
+        let mut trace = Vec::new();
+        let calls = self.parse_all(bytes);
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let Some(len) = calls[pos]
+                .filter(|call| call.height <= MAX_DEPTH)
+                .map(|call| call.len)
+            else {
+                pos += 1;
+                continue;
+            };
+            let expr = self.build(bytes, pos, &calls);
+            let span = pos..pos + len;
+            let overflow = || Overflow {
+                span: span.clone(),
+                width: self.width,
+            };
+            let Expr::Call { name, args } = &expr else {
+                unreachable!("build returns calls");
+            };
+            let status = match self.table[name] {
+                Operation::Toggle { apply, .. } => {
+                    let args = self.eval_args(args).ok_or_else(overflow)?;
+                    enabled = apply(enabled, &args);
+                    Status::Toggled(enabled)
+                }
+                Operation::Function { .. } if enabled => {
+                    let result = self.eval(&expr).ok_or_else(overflow)?;
+                    value = value
+                        .checked_add(result)
+                        .and_then(|v| self.check(v))
+                        .ok_or_else(overflow)?;
+                    Status::Executed(result)
+                }
+                Operation::Function { .. } => Status::Skipped,
+            };
+            trace.push(Step { span, status });
+            pos += len;
+        }
+        Ok(Execution { value, trace })
+    }
+
+    /// Return value if it fits in the accumulator width.
+    fn check(&self, value: i128) -> Option<i128> {
+        if self.width == 128 {
+            return Some(value);
+        }
+        let bound = 1_i128 << (self.width - 1);
+        (-bound..bound).contains(&value).then_some(value)
+    }
+
+    /// Evaluate expression, or None on overflow.
+    fn eval(&self, expr: &Expr) -> Option<i128> {
+        match expr {
+            Expr::Literal(v) => self.check(*v),
+            Expr::Call { name, args } => match self.table[name] {
+                Operation::Function { apply, .. } => {
+                    apply(&self.eval_args(args)?).and_then(|v| self.check(v))
+                }
+                Operation::Toggle { .. } => unreachable!("toggles are not nested"),
+            },
+        }
+    }
+
+    fn eval_args(&self, args: &[Expr]) -> Option<Vec<i128>> {
+        args.iter().map(|arg| self.eval(arg)).collect()
+    }
+
+    /// Find calls at every position of the memory dump.
+    ///
+    /// Positions are parsed right to left, so that calls nested in a call
+    /// are already known, and every position is parsed once.
+    fn parse_all(&self, bytes: &[u8]) -> Vec<Option<Shape>> {
+        let mut calls = vec![None; bytes.len()];
+        for pos in (0..bytes.len()).rev() {
+            calls[pos] = self
+                .parse_call(bytes, pos, &calls)
+                .map(|(shape, _, _)| shape);
+        }
+        calls
+    }
+
+    /// Parse call to a registered operation at `pos`, where `calls` are
+    /// the calls found after it.
+    ///
+    /// Arguments are numbers of 1-3 digits or nested function calls.
+    /// Returns shape, name and arguments of the call.
+    fn parse_call(
+        &self,
+        bytes: &[u8],
+        pos: usize,
+        calls: &[Option<Shape>],
+    ) -> Option<(Shape, &str, Vec<Arg>)> {
+        let rest = &bytes[pos..];
+        let (name, operation) = self.table.iter().find(|(name, _)| {
+            rest.starts_with(name.as_bytes()) && rest.get(name.len()) == Some(&b'(')
+        })?;
+        let mut len = name.len() + 1;
+        let mut height = 1;
+        let mut args = Vec::new();
+        while rest.get(len) != Some(&b')') {
+            if args.len() == operation.arity() {
+                return None;
+            }
+            if !args.is_empty() {
+                if rest.get(len) != Some(&b',') {
+                    return None;
+                }
+                len += 1;
+            }
+            let digits = rest[len..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            if digits > 0 {
+                if digits > 3 {
+                    return None;
+                }
+                let value = rest[len..len + digits]
+                    .iter()
+                    .fold(0, |acc, b| acc * 10 + (b - b'0') as i128);
+                args.push(Arg::Literal(value));
+                len += digits;
+            } else {
+                let nested = calls.get(pos + len).copied().flatten()?;
+                if nested.toggle {
+                    return None;
+                }
+                height = height.max(nested.height + 1);
+                args.push(Arg::Call(pos + len));
+                len += nested.len;
+            }
+        }
+        if args.len() != operation.arity() {
+            return None;
+        }
+        let shape = Shape {
+            len: len + 1,
+            height,
+            toggle: matches!(operation, Operation::Toggle { .. }),
+        };
+        Some((shape, name, args))
+    }
+
+    /// Build expression of the call at `pos`, found by [`Self::parse_all`].
+    fn build(&self, bytes: &[u8], pos: usize, calls: &[Option<Shape>]) -> Expr {
+        let (_, name, args) = self
+            .parse_call(bytes, pos, calls)
+            .expect("call was parsed before");
+        Expr::Call {
+            name: name.to_string(),
+            args: args
+                .into_iter()
+                .map(|arg| match arg {
+                    Arg::Literal(value) => Expr::Literal(value),
+                    Arg::Call(nested) => self.build(bytes, nested, calls),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SAMPLE2: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn standard_matches_part2() {
+        let execution = Interpreter::standard(32).run(SAMPLE2).unwrap();
+        assert_eq!(execution.value, 48);
+    }
+
+    #[test]
+    fn trace() {
+        let execution = Interpreter::standard(32).run(SAMPLE2).unwrap();
+        let statuses: Vec<Status> = execution.trace.iter().map(|s| s.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Status::Executed(8),
+                Status::Toggled(false),
+                Status::Skipped,
+                Status::Skipped,
+                Status::Toggled(true),
+                Status::Executed(40),
+            ]
+        );
+        assert_eq!(execution.trace[0].span, 1..9);
+    }
+
+    #[test]
+    fn nested_calls() {
+        let execution = Interpreter::extended(32)
+            .run("mul(add(1,2),sub(2,7))?add(mul(2,3)")
+            .unwrap();
+        assert_eq!(execution.value, -15 + 6);
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let interpreter = Interpreter::standard(64);
+        let nested = |depth| "mul(".repeat(depth) + "1" + &",1)".repeat(depth);
+        assert_eq!(interpreter.run(&nested(MAX_DEPTH)).unwrap().value, 1);
+        // Too deep, but the innermost calls are valid on their own.
+        let execution = interpreter.run(&nested(MAX_DEPTH + 1)).unwrap();
+        assert_eq!(execution.value, 1);
+        assert_eq!(execution.trace[0].span.start, 4);
+    }
+
+    #[test]
+    fn unclosed_nesting() {
+        let input = "mul(".repeat(200_000);
+        assert_eq!(Interpreter::extended(64).run(&input).unwrap().value, 0);
+    }
+
+    #[test]
+    fn conditional_toggle() {
+        let execution = Interpreter::extended(32)
+            .run("dont_if(sub(1,1))mul(2,2)dont_if(1)mul(3,3)do_if(0)mul(4,4)")
+            .unwrap();
+        assert_eq!(execution.value, 4);
+    }
+
+    #[test]
+    fn custom_operation() {
+        let mut interpreter = Interpreter::new(64);
+        interpreter.register(
+            "neg",
+            Operation::Function {
+                arity: 1,
+                apply: |args| args[0].checked_neg(),
+            },
+        );
+        assert_eq!(interpreter.run("neg(5)mul(2,2)").unwrap().value, -5);
+    }
+
+    #[test]
+    fn overflow() {
+        let interpreter = Interpreter::standard(8);
+        assert_eq!(interpreter.run("mul(11,11)").unwrap().value, 121);
+        assert_eq!(
+            interpreter.run("mul(2,4)mul(20,20)"),
+            Err(Overflow {
+                span: 8..18,
+                width: 8
+            })
+        );
+        assert_eq!(
+            interpreter.run("mul(10,10)mul(5,6)"),
+            Err(Overflow {
+                span: 10..18,
+                width: 8
+            })
+        );
+    }
+
+    #[test]
+    fn skipped_is_not_evaluated() {
+        let interpreter = Interpreter::standard(8);
+        assert_eq!(interpreter.run("don't()mul(20,20)").unwrap().value, 0);
+    }
+}
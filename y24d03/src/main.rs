@@ -1,26 +1,127 @@
-use regex::Regex;
-use std::io::{self, Read};
+use interpreter::{Interpreter, Status};
+use scanner::{Instruction, Scanner, StreamScanner};
+use std::{
+    env,
+    io::{self, Read},
+};
+
+mod interpreter;
+mod recovery;
+mod scanner;
 
 /// Day 3: Mull It Over
 ///
+/// Run with argument `tokens` to list the instructions found in memory with
+/// their byte spans, instead of the puzzle answers.
+///
+/// Run with argument `near-misses` to list malformed instructions with their
+/// offsets and defects, or `highlight` to print the memory dump with valid,
+/// disabled and malformed instructions in colour.
+///
+/// Run with argument `stream [chunk_size]` to solve part 2 reading input in
+/// chunks, without holding it all in memory.
+///
+/// Run with argument `interpret` to execute the memory dump with the extended
+/// instruction set of [`Interpreter::extended`]. Options `--width <bits>`
+/// (default 64) set the accumulator width, and `--trace` lists executed and
+/// skipped instructions.
+///
 /// <https://adventofcode.com/2024/day/3>
 fn main() -> io::Result<()> {
+    if env::args().nth(1).as_deref() == Some("stream") {
+        let chunk_size = env::args()
+            .nth(2)
+            .map_or(Some(4096), |s| s.parse().ok())
+            .filter(|n| *n > 0)
+            .ok_or_else(|| io::Error::other("chunk size must be a positive number"))?;
+        println!("Part 2: {}", part2_stream(io::stdin().lock(), chunk_size)?);
+        return Ok(());
+    }
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
+    if env::args().nth(1).as_deref() == Some("tokens") {
+        for token in Scanner::new(&input) {
+            println!(
+                "{}..{}\t{}",
+                token.span.start, token.span.end, token.instruction
+            );
+        }
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("near-misses") {
+        for near_miss in recovery::near_misses(&input) {
+            let defects: Vec<String> = near_miss.defects.iter().map(|d| d.to_string()).collect();
+            println!(
+                "{}..{}\t{}\t{}",
+                near_miss.span.start,
+                near_miss.span.end,
+                &input[near_miss.span.clone()],
+                defects.join(", ")
+            );
+        }
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("highlight") {
+        print!("{}", recovery::highlight(&input));
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("interpret") {
+        return interpret(&input, env::args().skip(2));
+    }
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
     Ok(())
 }
 
+/// Run interpreter on input, with options from command line.
+fn interpret(input: &str, mut args: impl Iterator<Item = String>) -> io::Result<()> {
+    let mut width = 64;
+    let mut trace = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => {
+                width = args
+                    .next()
+                    .and_then(|w| w.parse().ok())
+                    .filter(|w| (1..=128).contains(w))
+                    .ok_or_else(|| io::Error::other("--width requires 1..=128"))?;
+            }
+            "--trace" => trace = true,
+            _ => return Err(io::Error::other(format!("unknown option {}", arg))),
+        }
+    }
+    let execution = Interpreter::extended(width)
+        .run(input)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    if trace {
+        for step in &execution.trace {
+            let status = match step.status {
+                Status::Executed(value) => format!("= {}", value),
+                Status::Skipped => "skipped".to_string(),
+                Status::Toggled(true) => "enabled".to_string(),
+                Status::Toggled(false) => "disabled".to_string(),
+            };
+            println!(
+                "{}..{}\t{}\t{}",
+                step.span.start,
+                step.span.end,
+                &input[step.span.clone()],
+                status
+            );
+        }
+    }
+    println!("Result: {}", execution.value);
+    Ok(())
+}
+
 /// Process all `mul(a,b)` instructions.
 ///
 /// Returns sum(a * b) over all a,b pairs.
-fn part1(input: &str) -> i32 {
-    let re = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
-    re.captures_iter(input)
-        .map(|caps| {
-            let (_, [a, b]) = caps.extract();
-            a.parse::<i32>().unwrap() * b.parse::<i32>().unwrap()
+fn part1(input: &str) -> u32 {
+    Scanner::new(input)
+        .map(|token| match token.instruction {
+            Instruction::Mul(a, b) => a * b,
+            _ => 0,
         })
         .sum()
 }
@@ -31,27 +132,34 @@ fn part1(input: &str) -> i32 {
 /// and when meeting `don't()`, unset flag. Process only when enabled.
 ///
 /// Returns sum(a * b) over all a,b pairs when enabled.
-fn part2(input: &str) -> i32 {
-    let re_all = Regex::new(r"(?:mul\(\d+,\d+\)|do\(\)|don't\(\))").unwrap();
-    let re_mul = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
-    let mut result = 0;
+fn part2(input: &str) -> u32 {
     let mut enabled = true;
-    for (full, []) in re_all.captures_iter(input).map(|c| c.extract()) {
-        match full {
-            "do()" => {
-                enabled = true;
-            }
-            "don't()" => {
-                enabled = false;
-            }
-            _ if enabled => {
-                let (_, [a, b]) = re_mul.captures(full).unwrap().extract();
-                result += a.parse::<i32>().unwrap() * b.parse::<i32>().unwrap() as i32;
-            }
-            _ => {}
-        }
+    Scanner::new(input)
+        .map(|token| execute(&mut enabled, token.instruction))
+        .sum()
+}
+
+/// As part 2, but read input from `reader` in chunks of `chunk_size` bytes.
+fn part2_stream(reader: impl Read, chunk_size: usize) -> io::Result<u32> {
+    let mut enabled = true;
+    let mut result = 0;
+    for token in StreamScanner::new(reader, chunk_size) {
+        result += execute(&mut enabled, token?.instruction);
+    }
+    Ok(result)
+}
+
+/// Execute instruction, updating enabled flag.
+///
+/// Returns product of `mul(a,b)` if enabled, otherwise 0.
+fn execute(enabled: &mut bool, instruction: Instruction) -> u32 {
+    match instruction {
+        Instruction::Do => *enabled = true,
+        Instruction::Dont => *enabled = false,
+        Instruction::Mul(a, b) if *enabled => return a * b,
+        Instruction::Mul(..) => {}
     }
-    result
+    0
 }
 
 #[cfg(test)]
@@ -71,4 +179,12 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(SAMPLE2), 48);
     }
+
+    #[test]
+    fn test_part2_stream() {
+        for chunk_size in [1, 2, 3, 7, 64] {
+            let result = part2_stream(SAMPLE2.as_bytes(), chunk_size).unwrap();
+            assert_eq!(result, 48);
+        }
+    }
 }
@@ -0,0 +1,260 @@
+use crate::scanner::{Instruction, Scanner};
+use std::{fmt::Display, ops::Range};
+
+/// Way in which a near-instruction differs from a valid one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Defect {
+    /// Brackets other than `(` and `)`, such as `mul[3,7]`.
+    Bracket,
+    /// Whitespace around name, brackets or operands, such as `mul ( 2 , 4 )`.
+    Whitespace,
+    /// Operand with more than three digits.
+    LongOperand,
+    /// Wrong number of operands, or an empty operand.
+    Arity,
+    /// Operands are not followed by a closing bracket.
+    Unterminated,
+}
+
+impl Display for Defect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Defect::Bracket => "bracket",
+            Defect::Whitespace => "whitespace",
+            Defect::LongOperand => "long operand",
+            Defect::Arity => "arity",
+            Defect::Unterminated => "unterminated",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Malformed instruction, which the scanner ignores.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NearMiss {
+    pub span: Range<usize>,
+    pub defects: Vec<Defect>,
+}
+
+/// Instruction names and their number of operands.
+const KEYWORDS: [(&[u8], usize); 3] = [(b"don't", 0), (b"do", 0), (b"mul", 2)];
+
+/// Find malformed instructions in memory dump.
+///
+/// A near-instruction is an instruction name followed by an opening bracket
+/// `(`, `[`, `{` or `<`, possibly after whitespace. It extends over operands,
+/// commas and whitespace up to and including a closing bracket.
+pub fn near_misses(input: &str) -> Vec<NearMiss> {
+    let bytes = input.as_bytes();
+    let mut valid = Scanner::new(input).peekable();
+    let mut result = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        while valid.next_if(|t| t.span.end <= pos).is_some() {}
+        if let Some(token) = valid.peek().filter(|t| t.span.contains(&pos)) {
+            pos = token.span.end;
+            continue;
+        }
+        match parse_lenient(&bytes[pos..]) {
+            Some((len, defects)) if !defects.is_empty() => {
+                result.push(NearMiss {
+                    span: pos..pos + len,
+                    defects,
+                });
+                pos += len;
+            }
+            _ => pos += 1,
+        }
+    }
+    result
+}
+
+/// Parse near-instruction from the beginning of `bytes`.
+///
+/// Returns its length and defects, which are empty for valid instructions.
+fn parse_lenient(bytes: &[u8]) -> Option<(usize, Vec<Defect>)> {
+    let (keyword, arity) = KEYWORDS.iter().find(|(k, _)| bytes.starts_with(k))?;
+    let mut defects = Vec::new();
+    let mut pos = keyword.len();
+
+    if skip_whitespace(bytes, &mut pos) {
+        defects.push(Defect::Whitespace);
+    }
+    match bytes.get(pos)? {
+        b'(' => {}
+        b'[' | b'{' | b'<' => defects.push(Defect::Bracket),
+        _ => return None,
+    }
+    pos += 1;
+
+    let mut operands = Vec::new();
+    loop {
+        let ws_before = skip_whitespace(bytes, &mut pos);
+        let digits = bytes[pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        pos += digits;
+        let ws_after = skip_whitespace(bytes, &mut pos);
+        if ws_before || ws_after {
+            defects.push(Defect::Whitespace);
+        }
+        if digits > 3 {
+            defects.push(Defect::LongOperand);
+        }
+        operands.push(digits);
+        match bytes.get(pos) {
+            Some(b',') => pos += 1,
+            Some(b')') => {
+                pos += 1;
+                break;
+            }
+            Some(b']' | b'}' | b'>') => {
+                defects.push(Defect::Bracket);
+                pos += 1;
+                break;
+            }
+            _ => {
+                defects.push(Defect::Unterminated);
+                break;
+            }
+        }
+    }
+    if operands == [0] {
+        operands.clear();
+    }
+    if operands.len() != *arity || operands.contains(&0) {
+        defects.push(Defect::Arity);
+    }
+
+    let mut unique = Vec::with_capacity(defects.len());
+    for defect in defects {
+        if !unique.contains(&defect) {
+            unique.push(defect);
+        }
+    }
+    Some((pos, unique))
+}
+
+/// Advance `pos` over whitespace. Returns true if there was any.
+fn skip_whitespace(bytes: &[u8], pos: &mut usize) -> bool {
+    let n = bytes[*pos..]
+        .iter()
+        .take_while(|b| b.is_ascii_whitespace())
+        .count();
+    *pos += n;
+    n > 0
+}
+
+const RESET: &str = "\x1b[0m";
+const ENABLED: &str = "\x1b[1;32m";
+const DISABLED: &str = "\x1b[2;33m";
+const TOGGLE: &str = "\x1b[1;36m";
+const MALFORMED: &str = "\x1b[1;31m";
+
+/// Render memory dump with ANSI colours.
+///
+/// Enabled `mul` instructions are green, disabled ones yellow, `do()` and
+/// `don't()` cyan and near-instructions red.
+pub fn highlight(input: &str) -> String {
+    let mut spans: Vec<(Range<usize>, &str)> = Vec::new();
+    let mut enabled = true;
+    for token in Scanner::new(input) {
+        let colour = match token.instruction {
+            Instruction::Do => {
+                enabled = true;
+                TOGGLE
+            }
+            Instruction::Dont => {
+                enabled = false;
+                TOGGLE
+            }
+            Instruction::Mul(..) if enabled => ENABLED,
+            Instruction::Mul(..) => DISABLED,
+        };
+        spans.push((token.span, colour));
+    }
+    spans.extend(near_misses(input).into_iter().map(|n| (n.span, MALFORMED)));
+    spans.sort_by_key(|(span, _)| span.start);
+
+    let mut result = String::with_capacity(input.len());
+    let mut pos = 0;
+    for (span, colour) in spans {
+        result.push_str(&input[pos..span.start]);
+        result.push_str(colour);
+        result.push_str(&input[span.clone()]);
+        result.push_str(RESET);
+        pos = span.end;
+    }
+    result.push_str(&input[pos..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SAMPLE2: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn sample_near_misses() {
+        assert_eq!(
+            near_misses(SAMPLE2),
+            vec![
+                NearMiss {
+                    span: 10..18,
+                    defects: vec![Defect::Bracket]
+                },
+                NearMiss {
+                    span: 37..47,
+                    defects: vec![Defect::Bracket]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn classify() {
+        let defects = |s: &str| parse_lenient(s.as_bytes()).map(|(_, d)| d);
+        assert_eq!(defects("mul ( 2 , 4 )"), Some(vec![Defect::Whitespace]));
+        assert_eq!(defects("mul(1234,5)"), Some(vec![Defect::LongOperand]));
+        assert_eq!(defects("mul(4)"), Some(vec![Defect::Arity]));
+        assert_eq!(defects("mul(,4)"), Some(vec![Defect::Arity]));
+        assert_eq!(defects("do(1)"), Some(vec![Defect::Arity]));
+        assert_eq!(defects("don't[]"), Some(vec![Defect::Bracket]));
+        assert_eq!(defects("mul(5,5+"), Some(vec![Defect::Unterminated]));
+        assert_eq!(defects("mul(2,4)"), Some(vec![]));
+        assert_eq!(defects("do_not_mul"), None);
+    }
+
+    #[test]
+    fn near_miss_span() {
+        let input = "xmul ( 2 , 4 )+mul(5,5";
+        assert_eq!(
+            near_misses(input),
+            vec![
+                NearMiss {
+                    span: 1..14,
+                    defects: vec![Defect::Whitespace]
+                },
+                NearMiss {
+                    span: 15..22,
+                    defects: vec![Defect::Unterminated]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let rendered = highlight("mul(1,2)don't()mul[3,4]mul(5,6)");
+        assert_eq!(
+            rendered,
+            format!(
+                "{ENABLED}mul(1,2){RESET}{TOGGLE}don't(){RESET}\
+                 {MALFORMED}mul[3,4]{RESET}{DISABLED}mul(5,6){RESET}"
+            )
+        );
+    }
+}
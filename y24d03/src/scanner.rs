@@ -0,0 +1,309 @@
+use std::{
+    fmt::Display,
+    io::{self, Read},
+    ops::Range,
+};
+
+/// Instruction found in corrupted memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    /// `mul(a,b)`, where operands have 1-3 digits.
+    Mul(u32, u32),
+    /// `do()`
+    Do,
+    /// `don't()`
+    Dont,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Mul(a, b) => write!(f, "mul({},{})", a, b),
+            Instruction::Do => write!(f, "do()"),
+            Instruction::Dont => write!(f, "don't()"),
+        }
+    }
+}
+
+/// Instruction with its byte span in the memory dump.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub instruction: Instruction,
+    pub span: Range<usize>,
+}
+
+/// Iterator over instructions in a memory dump.
+///
+/// Bytes that do not start a valid instruction are skipped one at a time,
+/// so instructions may begin inside the remains of a broken one.
+pub struct Scanner<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Scanner {
+            input: input.as_bytes(),
+            pos: 0,
+        }
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.input.len() {
+            let start = self.pos;
+            if let Some((instruction, len)) = parse_instruction(&self.input[start..]) {
+                self.pos += len;
+                return Some(Token {
+                    instruction,
+                    span: start..start + len,
+                });
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+/// Iterator over instructions read from a source in fixed-size chunks.
+///
+/// Yields the same tokens as [`Scanner`] on the whole input. An instruction
+/// that may continue past the end of the current chunk is kept in the buffer
+/// until the next chunk has been read.
+pub struct StreamScanner<R: Read> {
+    reader: R,
+    chunk_size: usize,
+    buf: Vec<u8>,
+    /// Absolute offset of `buf[0]` in the source.
+    offset: usize,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> StreamScanner<R> {
+    pub fn new(reader: R, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        StreamScanner {
+            reader,
+            chunk_size,
+            buf: Vec::with_capacity(chunk_size + MAX_INSTRUCTION_LEN),
+            offset: 0,
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Drop processed bytes from the buffer and read next chunk after the
+    /// remaining ones.
+    fn refill(&mut self) -> io::Result<()> {
+        self.buf.drain(..self.pos);
+        self.offset += self.pos;
+        self.pos = 0;
+        let len = self.buf.len();
+        self.buf.resize(len + self.chunk_size, 0);
+        let n = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        };
+        self.buf.truncate(len + n);
+        self.eof = n == 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for StreamScanner<R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pos < self.buf.len() {
+                let start = self.pos;
+                match try_parse(&self.buf[start..]) {
+                    Parse::Match(instruction, len) => {
+                        self.pos += len;
+                        let start = self.offset + start;
+                        return Some(Ok(Token {
+                            instruction,
+                            span: start..start + len,
+                        }));
+                    }
+                    Parse::Incomplete if !self.eof => break,
+                    Parse::Incomplete | Parse::NoMatch => self.pos += 1,
+                }
+            }
+            if self.eof {
+                return None;
+            }
+            if let Err(e) = self.refill() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Length of the longest instruction, `mul(123,456)`.
+const MAX_INSTRUCTION_LEN: usize = 12;
+
+/// Outcome of parsing an instruction from the beginning of a buffer.
+#[derive(Debug, PartialEq)]
+enum Parse {
+    /// Instruction and its length in bytes.
+    Match(Instruction, usize),
+    NoMatch,
+    /// Buffer ends before it can be decided whether instruction matches.
+    Incomplete,
+}
+
+/// Parse instruction from the beginning of `bytes`.
+///
+/// Returns instruction and its length in bytes.
+pub fn parse_instruction(bytes: &[u8]) -> Option<(Instruction, usize)> {
+    match try_parse(bytes) {
+        Parse::Match(instruction, len) => Some((instruction, len)),
+        _ => None,
+    }
+}
+
+fn try_parse(bytes: &[u8]) -> Parse {
+    let candidates = [
+        literal(bytes, b"do()").map(|len| (Instruction::Do, len)),
+        literal(bytes, b"don't()").map(|len| (Instruction::Dont, len)),
+        parse_mul(bytes),
+    ];
+    let mut result = Parse::NoMatch;
+    for candidate in candidates {
+        match candidate {
+            Ok((instruction, len)) => return Parse::Match(instruction, len),
+            Err(Parse::Incomplete) => result = Parse::Incomplete,
+            Err(_) => {}
+        }
+    }
+    result
+}
+
+/// Parse `mul(a,b)` from the beginning of `bytes`.
+fn parse_mul(bytes: &[u8]) -> Result<(Instruction, usize), Parse> {
+    let mut len = literal(bytes, b"mul(")?;
+    let (a, a_len) = parse_operand(&bytes[len..])?;
+    len += a_len;
+    len += literal(&bytes[len..], b",")?;
+    let (b, b_len) = parse_operand(&bytes[len..])?;
+    len += b_len;
+    len += literal(&bytes[len..], b")")?;
+    Ok((Instruction::Mul(a, b), len))
+}
+
+/// Match literal `lit` at the beginning of `bytes`, returning its length.
+fn literal(bytes: &[u8], lit: &[u8]) -> Result<usize, Parse> {
+    if bytes.starts_with(lit) {
+        Ok(lit.len())
+    } else if lit.starts_with(bytes) {
+        Err(Parse::Incomplete)
+    } else {
+        Err(Parse::NoMatch)
+    }
+}
+
+/// Parse operand of 1-3 digits from the beginning of `bytes`.
+///
+/// Returns value and number of digits.
+fn parse_operand(bytes: &[u8]) -> Result<(u32, usize), Parse> {
+    let len = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    if len > 3 {
+        return Err(Parse::NoMatch);
+    }
+    if len == bytes.len() {
+        return Err(Parse::Incomplete);
+    }
+    if len == 0 {
+        return Err(Parse::NoMatch);
+    }
+    let value = bytes[..len]
+        .iter()
+        .fold(0, |acc, b| acc * 10 + (b - b'0') as u32);
+    Ok((value, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let tokens: Vec<Token> =
+            Scanner::new("xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+undo()?").collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    instruction: Instruction::Mul(2, 4),
+                    span: 1..9
+                },
+                Token {
+                    instruction: Instruction::Dont,
+                    span: 20..27
+                },
+                Token {
+                    instruction: Instruction::Mul(5, 5),
+                    span: 28..36
+                },
+                Token {
+                    instruction: Instruction::Do,
+                    span: 39..43
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn operand_digits() {
+        assert_eq!(
+            parse_instruction(b"mul(123,4)"),
+            Some((Instruction::Mul(123, 4), 10))
+        );
+        assert_eq!(parse_instruction(b"mul(1234,4)"), None);
+        assert_eq!(parse_instruction(b"mul(,4)"), None);
+        assert_eq!(parse_instruction(b"mul(4,5"), None);
+    }
+
+    #[test]
+    fn restart_inside_broken() {
+        let tokens: Vec<Instruction> = Scanner::new("mul(mul(1,2)")
+            .map(|t| t.instruction)
+            .collect();
+        assert_eq!(tokens, vec![Instruction::Mul(1, 2)]);
+    }
+
+    #[test]
+    fn incomplete() {
+        assert_eq!(try_parse(b"do"), Parse::Incomplete);
+        assert_eq!(try_parse(b"don'"), Parse::Incomplete);
+        assert_eq!(try_parse(b"mul(12"), Parse::Incomplete);
+        assert_eq!(try_parse(b"mul(123,4"), Parse::Incomplete);
+        assert_eq!(try_parse(b"mul(1234"), Parse::NoMatch);
+        assert_eq!(try_parse(b"mux"), Parse::NoMatch);
+    }
+
+    #[test]
+    fn stream_matches_whole_input() {
+        let input =
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))mul(123,456)";
+        let expected: Vec<Token> = Scanner::new(input).collect();
+        for chunk_size in 1..=input.len() {
+            let tokens: Vec<Token> = StreamScanner::new(input.as_bytes(), chunk_size)
+                .collect::<io::Result<_>>()
+                .unwrap();
+            assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+        }
+    }
+}
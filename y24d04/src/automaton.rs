@@ -0,0 +1,128 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Aho-Corasick automaton for finding many patterns in one pass.
+///
+/// Text is fed one character at a time with [`Automaton::step`]. After each
+/// step, [`Automaton::outputs`] lists the patterns ending at that character.
+pub struct Automaton {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    /// Indices of patterns, which end at the node.
+    outputs: Vec<Vec<usize>>,
+}
+
+impl Automaton {
+    /// Start state, where no characters have been matched.
+    pub const START: usize = 0;
+
+    /// Build automaton from patterns. Empty patterns never match.
+    pub fn new<I: IntoIterator<Item = Vec<char>>>(patterns: I) -> Self {
+        let mut automaton = Automaton {
+            goto: vec![HashMap::new()],
+            fail: vec![Self::START],
+            outputs: vec![Vec::new()],
+        };
+        for (p, pattern) in patterns.into_iter().enumerate() {
+            if pattern.is_empty() {
+                continue;
+            }
+            let mut node = Self::START;
+            for c in pattern {
+                node = match automaton.goto[node].get(&c) {
+                    Some(next) => *next,
+                    None => {
+                        let next = automaton.goto.len();
+                        automaton.goto.push(HashMap::new());
+                        automaton.fail.push(Self::START);
+                        automaton.outputs.push(Vec::new());
+                        automaton.goto[node].insert(c, next);
+                        next
+                    }
+                };
+            }
+            automaton.outputs[node].push(p);
+        }
+        automaton.build_failure_links();
+        automaton
+    }
+
+    /// Link each node to the node of its longest proper suffix, and collect
+    /// outputs of the suffixes. Nodes are processed in breadth first order,
+    /// so that links of shorter prefixes are complete.
+    fn build_failure_links(&mut self) {
+        let mut queue: VecDeque<usize> = self.goto[Self::START].values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> =
+                self.goto[node].iter().map(|(c, n)| (*c, *n)).collect();
+            for (c, child) in children {
+                let mut fallback = self.fail[node];
+                while fallback != Self::START && !self.goto[fallback].contains_key(&c) {
+                    fallback = self.fail[fallback];
+                }
+                let link = match self.goto[fallback].get(&c) {
+                    Some(n) if *n != child => *n,
+                    _ => Self::START,
+                };
+                self.fail[child] = link;
+                let inherited = self.outputs[link].clone();
+                self.outputs[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
+
+    /// Advance from `state` by character `c`.
+    pub fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(next) = self.goto[state].get(&c) {
+                return *next;
+            }
+            if state == Self::START {
+                return Self::START;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    /// Patterns ending at the last character fed to reach `state`.
+    pub fn outputs(&self, state: usize) -> &[usize] {
+        &self.outputs[state]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return (pattern, end index) pairs.
+    fn find_all(automaton: &Automaton, text: &str) -> Vec<(usize, usize)> {
+        let mut state = Automaton::START;
+        let mut result = Vec::new();
+        for (i, c) in text.chars().enumerate() {
+            state = automaton.step(state, c);
+            result.extend(automaton.outputs(state).iter().map(|p| (*p, i)));
+        }
+        result
+    }
+
+    #[test]
+    fn overlapping_patterns() {
+        let patterns = ["he", "she", "his", "hers"];
+        let automaton = Automaton::new(patterns.iter().map(|p| p.chars().collect()));
+        let mut found = find_all(&automaton, "ushers");
+        found.sort();
+        assert_eq!(found, vec![(0, 3), (1, 3), (3, 5)]);
+    }
+
+    #[test]
+    fn repeated_and_empty_patterns() {
+        let patterns = ["aa", "", "aa", "a"];
+        let automaton = Automaton::new(patterns.iter().map(|p| p.chars().collect()));
+        let mut found = find_all(&automaton, "aaa");
+        found.sort();
+        assert_eq!(
+            found,
+            vec![(0, 1), (0, 2), (2, 1), (2, 2), (3, 0), (3, 1), (3, 2)]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+/// Rectangular 2D pattern, matched against the grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel(Vec<Vec<char>>);
+
+impl Kernel {
+    /// Parse kernel from lines of text.
+    ///
+    /// Returns None if text is empty or lines are of unequal length.
+    pub fn from_text(text: &str) -> Option<Self> {
+        let cells: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        if cells.is_empty()
+            || cells[0].is_empty()
+            || cells.iter().any(|r| r.len() != cells[0].len())
+        {
+            return None;
+        }
+        Some(Kernel(cells))
+    }
+
+    /// Kernel dimensions (i, j).
+    pub fn shape(&self) -> (usize, usize) {
+        (self.0.len(), self.0[0].len())
+    }
+
+    /// Kernel rotated clockwise.
+    pub fn rotated(&self) -> Self {
+        Kernel(rotated(&self.0))
+    }
+
+    /// Kernel mirrored left to right.
+    pub fn reflected(&self) -> Self {
+        Kernel(
+            self.0
+                .iter()
+                .map(|row| row.iter().rev().copied().collect())
+                .collect(),
+        )
+    }
+
+    /// Distinct rotations of the kernel, starting from the kernel itself.
+    pub fn rotations(&self) -> Vec<Self> {
+        let mut kernels = vec![self.clone()];
+        for _ in 0..3 {
+            let next = kernels.last().unwrap().rotated();
+            if !kernels.contains(&next) {
+                kernels.push(next);
+            }
+        }
+        kernels
+    }
+
+    /// Distinct rotations and reflections of the kernel, i.e. its images
+    /// under the dihedral group of the square.
+    pub fn symmetries(&self) -> Vec<Self> {
+        let mut kernels = self.rotations();
+        for kernel in self.reflected().rotations() {
+            if !kernels.contains(&kernel) {
+                kernels.push(kernel);
+            }
+        }
+        kernels
+    }
+}
+
+/// Kernel found in the grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KernelMatch {
+    /// Position (i, j) of the top left corner of the kernel.
+    pub position: (usize, usize),
+    /// Index of the matching kernel in the matcher.
+    pub kernel: usize,
+}
+
+/// Matches a set of kernels, where wildcard characters match any cell.
+pub struct Matcher {
+    kernels: Vec<Kernel>,
+    wildcards: Vec<char>,
+}
+
+impl Matcher {
+    /// Create matcher with `.` as the wildcard.
+    pub fn new(kernels: Vec<Kernel>) -> Self {
+        Matcher {
+            kernels,
+            wildcards: vec!['.'],
+        }
+    }
+
+    /// Set characters, which match any cell.
+    pub fn wildcards(mut self, wildcards: &[char]) -> Self {
+        self.wildcards = wildcards.to_vec();
+        self
+    }
+
+    /// Find all kernel matches in the grid.
+    ///
+    /// Returns matches ordered by kernel, then by position.
+    pub fn find(&self, grid: &[Vec<char>]) -> Vec<KernelMatch> {
+        let mut matches = Vec::new();
+        for (k, kernel) in self.kernels.iter().enumerate() {
+            let (h, w) = kernel.shape();
+            if grid.len() < h {
+                continue;
+            }
+            for i0 in 0..grid.len() - h + 1 {
+                for j0 in 0..(grid[i0].len() + 1).saturating_sub(w) {
+                    if self.slice_matches(grid, i0, j0, kernel) {
+                        matches.push(KernelMatch {
+                            position: (i0, j0),
+                            kernel: k,
+                        });
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    /// Positions (i, j) of non-wildcard cells in the match.
+    pub fn cells(&self, m: &KernelMatch) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (i, row) in self.kernels[m.kernel].0.iter().enumerate() {
+            for (j, k) in row.iter().enumerate() {
+                if !self.wildcards.contains(k) {
+                    cells.push((m.position.0 + i, m.position.1 + j));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Does the slice starting at (i0, j0) match?
+    fn slice_matches(&self, arr: &[Vec<char>], i0: usize, j0: usize, kernel: &Kernel) -> bool {
+        for (i, row) in kernel.0.iter().enumerate() {
+            for (j, k) in row.iter().enumerate() {
+                if self.wildcards.contains(k) {
+                    continue;
+                };
+                if arr[i + i0].get(j + j0) != Some(k) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Rotate 2D array clockwise
+fn rotated(arr: &[Vec<char>]) -> Vec<Vec<char>> {
+    let new_width = arr.len();
+    let new_height = 0..arr[0].len();
+    let mut result: Vec<Vec<char>> = (new_height)
+        .map(|_| (0..new_width).map(|_| '.').collect())
+        .collect();
+    for (i, row) in arr.iter().enumerate() {
+        for (j, item) in row.iter().enumerate() {
+            result[j][new_width - i - 1] = *item;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate() {
+        let arr: Vec<Vec<char>> = "
+12
+34
+56"
+        .trim()
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect();
+        let arr2 = rotated(&arr);
+        let expected: Vec<Vec<char>> = "
+531
+642"
+        .trim()
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect();
+        assert_eq!(arr2, expected);
+    }
+
+    #[test]
+    fn symmetry_deduplication() {
+        let x_mas = Kernel::from_text("M.S\n.A.\nM.S").unwrap();
+        assert_eq!(x_mas.symmetries().len(), 4);
+        let l = Kernel::from_text("X.\nXX").unwrap();
+        assert_eq!(l.symmetries().len(), 4);
+        let asymmetric = Kernel::from_text("AB\nC.").unwrap();
+        assert_eq!(asymmetric.symmetries().len(), 8);
+        let square = Kernel::from_text("AA\nAA").unwrap();
+        assert_eq!(square.symmetries().len(), 1);
+    }
+
+    #[test]
+    fn ragged_kernel() {
+        assert_eq!(Kernel::from_text("AB\nC"), None);
+        assert_eq!(Kernel::from_text(""), None);
+    }
+
+    #[test]
+    fn wildcards_and_positions() {
+        let grid: Vec<Vec<char>> = ["AXB", "AYB"].iter().map(|r| r.chars().collect()).collect();
+        let kernel = Kernel::from_text("A?\nA?").unwrap();
+        let matcher = Matcher::new(vec![kernel.clone()]);
+        assert!(matcher.find(&grid).is_empty());
+        let matcher = Matcher::new(kernel.symmetries()).wildcards(&['?']);
+        let matches = matcher.find(&grid);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].position, (0, 0));
+        assert_eq!(matcher.cells(&matches[0]), vec![(0, 0), (1, 0)]);
+    }
+}
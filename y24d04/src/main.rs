@@ -1,118 +1,167 @@
-use std::io::{self, Read};
+use kernel::{Kernel, Matcher};
+use search::{direction_name, find_words_in_lines};
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    io::{self, Read},
+};
+
+mod automaton;
+mod kernel;
+mod render;
+mod search;
 
 /// Day 4: Ceres Search
 ///
+/// Run with arguments `search <word>...` to list every match of the words
+/// in the grid, or `search --dict <file>` to read the words from a file with
+/// one word per line.
+///
+/// Run with arguments `kernel <file> [wildcards]` to list positions, where
+/// the pattern in the file or any of its rotations and reflections matches.
+/// Characters in `wildcards` (default `.`) match any cell.
+///
+/// Run with arguments `render <xmas|x-mas> [--colour]` to print the grid with
+/// only the cells of part 1 or part 2 matches, or with each match coloured.
+///
 /// <https://adventofcode.com/2024/day/4>
 fn main() -> io::Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
-    Ok(())
-}
-
-/// Find 'XMAS' strings in input in any cardinal or diagonal direction
-///
-/// Returns number of matches.
-fn part1(input: &str) -> u32 {
-    let chars: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
-    let dirs: Vec<(i32, i32)> = vec![
-        (0, 1),
-        (0, -1),
-        (1, 0),
-        (-1, 0),
-        (-1, 1),
-        (1, -1),
-        (-1, -1),
-        (1, 1),
-    ];
-    let mut matches = 0;
-    for i0 in 0..chars.len() {
-        for j0 in 0..chars[0].len() {
-            'dir_search: for (di, dj) in &dirs {
-                let i_end = (i0 as i32) + di * 3;
-                let j_end = (j0 as i32) + dj * 3;
-                if i_end < 0
-                    || j_end < 0
-                    || i_end as usize >= chars.len()
-                    || j_end as usize >= chars[0].len()
-                {
-                    continue;
-                };
-                for (k, mc) in ['X', 'M', 'A', 'S'].iter().enumerate() {
-                    let i = ((i0 as i32) + di * (k as i32)) as usize;
-                    let j = ((j0 as i32) + dj * (k as i32)) as usize;
-                    if chars[i][j] != *mc {
-                        continue 'dir_search;
-                    };
-                }
-                matches += 1;
-            }
+    if env::args().nth(1).as_deref() == Some("search") {
+        let mut words: Vec<String> = env::args().skip(2).collect();
+        if words.first().map(String::as_str) == Some("--dict") {
+            let path = words
+                .get(1)
+                .ok_or_else(|| io::Error::other("--dict requires a file"))?;
+            words = read_dictionary(&fs::read_to_string(path)?);
         }
+        let grid = parse_grid(&input).map_err(io::Error::other)?;
+        for m in find_words_in_lines(&grid, &words) {
+            println!(
+                "{},{}\t{}\t{}",
+                m.start.0,
+                m.start.1,
+                direction_name(m.direction),
+                m.word
+            );
+        }
+        return Ok(());
     }
-    matches
-}
-
-/// Find X-MAS patterns in input, return number of matches.
-fn part2(input: &str) -> u32 {
-    let chars: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
-    let kernel = vec![
-        vec!['M', '.', 'S'],
-        vec!['.', 'A', '.'],
-        vec!['M', '.', 'S'],
-    ];
-    let mut kernels = vec![kernel];
-    for _ in 0..3 {
-        kernels.push(rotated(kernels.last().unwrap()));
+    if env::args().nth(1).as_deref() == Some("kernel") {
+        let path = env::args()
+            .nth(2)
+            .ok_or_else(|| io::Error::other("kernel requires a file"))?;
+        let kernel = Kernel::from_text(&fs::read_to_string(path)?)
+            .ok_or_else(|| io::Error::other("kernel must be a non-empty rectangle"))?;
+        let mut matcher = Matcher::new(kernel.symmetries());
+        if let Some(wildcards) = env::args().nth(3) {
+            matcher = matcher.wildcards(&wildcards.chars().collect::<Vec<char>>());
+        }
+        let grid = parse_grid(&input).map_err(io::Error::other)?;
+        for m in matcher.find(&grid) {
+            println!("{},{}\t{}", m.position.0, m.position.1, m.kernel);
+        }
+        return Ok(());
     }
-
-    let mut matches = 0;
-    for kernel in &kernels {
-        for i0 in 0..chars.len() - 3 + 1 {
-            for j0 in 0..chars[0].len() - 3 + 1 {
-                if slice_matches(&chars, i0, j0, (3, 3), kernel) {
-                    matches += 1;
-                }
+    if env::args().nth(1).as_deref() == Some("render") {
+        let grid = parse_grid(&input).map_err(io::Error::other)?;
+        let matches: Vec<Vec<(usize, usize)>> = match env::args().nth(2).as_deref() {
+            Some("xmas") => find_words_in_lines(&grid, &["XMAS"])
+                .iter()
+                .map(|m| m.cells())
+                .collect(),
+            Some("x-mas") => {
+                let matcher = x_mas_matcher();
+                matcher
+                    .find(&grid)
+                    .iter()
+                    .map(|m| matcher.cells(m))
+                    .collect()
             }
+            _ => return Err(io::Error::other("render requires xmas or x-mas")),
+        };
+        if env::args().nth(3).as_deref() == Some("--colour") {
+            print!("{}", render::render_colour(&grid, &matches));
+        } else {
+            print!("{}", render::render_plain(&grid, &matches));
         }
+        return Ok(());
     }
-    matches
+    println!("Part 1: {}", part1(&input).map_err(io::Error::other)?);
+    println!("Part 2: {}", part2(&input).map_err(io::Error::other)?);
+    Ok(())
 }
 
-/// Does the slice match?
-fn slice_matches(
-    arr: &[Vec<char>],
-    i0: usize,
-    j0: usize,
-    size: (usize, usize),
-    kernel: &[Vec<char>],
-) -> bool {
-    for i in 0..size.0 {
-        for j in 0..size.1 {
-            if kernel[i][j] == '.' {
-                continue;
-            };
-            if arr[i + i0][j + j0] != kernel[i][j] {
-                return false;
-            }
-        }
+/// Grid rows are of unequal length.
+#[derive(Debug, PartialEq)]
+struct RaggedGrid {
+    row: usize,
+    len: usize,
+    expected: usize,
+}
+
+impl Display for RaggedGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "row {} has length {}, expected {}",
+            self.row, self.len, self.expected
+        )
     }
-    true
 }
 
-/// Rotate 2D array clockwise
-fn rotated(arr: &[Vec<char>]) -> Vec<Vec<char>> {
-    let new_width = arr.len();
-    let new_height = 0..arr[0].len();
-    let mut result: Vec<Vec<char>> = (new_height)
-        .map(|_| (0..new_width).map(|_| '.').collect())
+impl std::error::Error for RaggedGrid {}
+
+/// Parse rectangular grid of characters. Trailing empty lines are ignored,
+/// and empty input results in an empty grid.
+fn parse_grid(input: &str) -> Result<Vec<Vec<char>>, RaggedGrid> {
+    let grid: Vec<Vec<char>> = input
+        .trim_end_matches(['\n', '\r'])
+        .lines()
+        .map(|line| line.chars().collect())
         .collect();
-    for (i, row) in arr.iter().enumerate() {
-        for (j, item) in row.iter().enumerate() {
-            result[j][new_width - i - 1] = *item;
+    if let Some(first) = grid.first() {
+        let expected = first.len();
+        if let Some((row, r)) = grid.iter().enumerate().find(|(_, r)| r.len() != expected) {
+            return Err(RaggedGrid {
+                row,
+                len: r.len(),
+                expected,
+            });
         }
     }
-    result
+    Ok(grid)
+}
+
+/// Words from a dictionary, one per line. Empty lines are skipped.
+fn read_dictionary(dict: &str) -> Vec<String> {
+    dict.lines()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Find 'XMAS' strings in input in any cardinal or diagonal direction
+///
+/// Returns number of matches.
+fn part1(input: &str) -> Result<u32, RaggedGrid> {
+    Ok(find_words_in_lines(&parse_grid(input)?, &["XMAS"]).len() as u32)
+}
+
+/// Find X-MAS patterns in input, return number of matches.
+///
+/// Grids smaller than the pattern have no matches.
+fn part2(input: &str) -> Result<u32, RaggedGrid> {
+    Ok(x_mas_matcher().find(&parse_grid(input)?).len() as u32)
+}
+
+/// Matcher for two `MAS` crossing in the shape of an X.
+fn x_mas_matcher() -> Matcher {
+    let kernel = Kernel::from_text("M.S\n.A.\nM.S").unwrap();
+    Matcher::new(kernel.symmetries())
 }
 
 #[cfg(test)]
@@ -147,43 +196,47 @@ SAXAMASAA
 MAMMMXMMM";
 
     #[test]
-    fn test_rotate() {
-        let arr: Vec<Vec<char>> = "
-12
-34
-56"
-        .trim()
-        .lines()
-        .map(|line| line.chars().collect())
-        .collect();
-        let arr2 = rotated(&arr);
-        let expected: Vec<Vec<char>> = "
-531
-642"
-        .trim()
-        .lines()
-        .map(|line| line.chars().collect())
-        .collect();
-        assert_eq!(arr2, expected);
+    fn test_part1_1() {
+        assert_eq!(part1(SAMPLE1), Ok(4));
     }
 
     #[test]
-    fn test_part1_1() {
-        assert_eq!(part1(SAMPLE1), 4);
+    fn test_part1_2() {
+        assert_eq!(part1(SAMPLE2), Ok(18));
     }
 
     #[test]
-    fn test_part1_2() {
-        assert_eq!(part1(SAMPLE2), 18);
+    fn test_read_dictionary() {
+        assert_eq!(read_dictionary("XMAS\n\n SAM \n"), vec!["XMAS", "SAM"]);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(SAMPLE2), 9);
+        assert_eq!(part2(SAMPLE2), Ok(9));
     }
 
     #[test]
     fn test_part2_edges() {
-        assert_eq!(part2(SAMPLE3), 9);
+        assert_eq!(part2(SAMPLE3), Ok(9));
+    }
+
+    #[test]
+    fn tiny_grids() {
+        for input in ["", "\n", "X", "XM\nAS", "XMAS\n", "M\nA\nS"] {
+            assert_eq!(part2(input), Ok(0), "{:?}", input);
+        }
+        assert_eq!(part1(""), Ok(0));
+        assert_eq!(part1("XMAS\n\n"), Ok(1));
+    }
+
+    #[test]
+    fn ragged_grid() {
+        let expected = Err(RaggedGrid {
+            row: 1,
+            len: 2,
+            expected: 3,
+        });
+        assert_eq!(part1("M.S\n.A\nM.S"), expected);
+        assert_eq!(part2("M.S\n.A\nM.S"), expected);
     }
 }
@@ -0,0 +1,77 @@
+/// ANSI colours cycled through for consecutive matches.
+const PALETTE: [&str; 6] = [
+    "\x1b[1;31m",
+    "\x1b[1;32m",
+    "\x1b[1;33m",
+    "\x1b[1;34m",
+    "\x1b[1;35m",
+    "\x1b[1;36m",
+];
+const RESET: &str = "\x1b[0m";
+
+/// Render grid, replacing every cell not in any match with `.`.
+///
+/// * `matches` - Positions (i, j) of cells in each match.
+pub fn render_plain(grid: &[Vec<char>], matches: &[Vec<(usize, usize)>]) -> String {
+    let mut keep: Vec<Vec<bool>> = grid.iter().map(|row| vec![false; row.len()]).collect();
+    for (i, j) in matches.iter().flatten() {
+        keep[*i][*j] = true;
+    }
+    let mut result = String::new();
+    for (row, keep_row) in grid.iter().zip(keep) {
+        for (c, k) in row.iter().zip(keep_row) {
+            result.push(if k { *c } else { '.' });
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Render grid, colouring cells of each match with a colour of its own.
+///
+/// Cells shared by several matches take the colour of the last one.
+///
+/// * `matches` - Positions (i, j) of cells in each match.
+pub fn render_colour(grid: &[Vec<char>], matches: &[Vec<(usize, usize)>]) -> String {
+    let mut colours: Vec<Vec<Option<&str>>> =
+        grid.iter().map(|row| vec![None; row.len()]).collect();
+    for (m, cells) in matches.iter().enumerate() {
+        for (i, j) in cells {
+            colours[*i][*j] = Some(PALETTE[m % PALETTE.len()]);
+        }
+    }
+    let mut result = String::new();
+    for (row, colour_row) in grid.iter().zip(colours) {
+        for (c, colour) in row.iter().zip(colour_row) {
+            match colour {
+                Some(colour) => {
+                    result.push_str(colour);
+                    result.push(*c);
+                    result.push_str(RESET);
+                }
+                None => result.push(*c),
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_plain() {
+        let grid: Vec<Vec<char>> = ["ABC", "DEF"].iter().map(|r| r.chars().collect()).collect();
+        let rendered = render_plain(&grid, &[vec![(0, 0), (1, 1)], vec![(1, 2)]]);
+        assert_eq!(rendered, "A..\n.EF\n");
+    }
+
+    #[test]
+    fn test_render_colour() {
+        let grid: Vec<Vec<char>> = ["AB"].iter().map(|r| r.chars().collect()).collect();
+        let rendered = render_colour(&grid, &[vec![(0, 1)]]);
+        assert_eq!(rendered, format!("A{}B{}\n", PALETTE[0], RESET));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::automaton::Automaton;
+
+/// Search directions (di, dj): cardinal and diagonal.
+pub const DIRECTIONS: [(i32, i32); 8] = [
+    (0, 1),
+    (0, -1),
+    (1, 0),
+    (-1, 0),
+    (-1, 1),
+    (1, -1),
+    (-1, -1),
+    (1, 1),
+];
+
+/// Word found in the grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordMatch {
+    /// Position (i, j) of the first letter.
+    pub start: (usize, usize),
+    /// Step (di, dj) from one letter to the next.
+    pub direction: (i32, i32),
+    pub word: String,
+}
+
+impl WordMatch {
+    /// Positions (i, j) of all letters in the match.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        (0..self.word.chars().count() as i32)
+            .map(|k| {
+                (
+                    (self.start.0 as i32 + self.direction.0 * k) as usize,
+                    (self.start.1 as i32 + self.direction.1 * k) as usize,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Compass name of a direction, where north is up.
+pub fn direction_name(direction: (i32, i32)) -> &'static str {
+    match direction {
+        (-1, 0) => "N",
+        (-1, 1) => "NE",
+        (0, 1) => "E",
+        (1, 1) => "SE",
+        (1, 0) => "S",
+        (1, -1) => "SW",
+        (0, -1) => "W",
+        (-1, -1) => "NW",
+        _ => "?",
+    }
+}
+
+/// Find words in any cardinal or diagonal direction.
+///
+/// Empty words are ignored, and one-letter words are reported once per
+/// cell, in the first direction.
+///
+/// Every row, column and diagonal is scanned once with an automaton for all
+/// words and their reversals, so time is linear in grid size and total
+/// length of words, regardless of the number of words.
+///
+/// Returns matches ordered by start position.
+pub fn find_words_in_lines<S: AsRef<str>>(grid: &[Vec<char>], words: &[S]) -> Vec<WordMatch> {
+    let words: Vec<Vec<char>> = words
+        .iter()
+        .map(|w| w.as_ref().chars().collect())
+        .filter(|w: &Vec<char>| !w.is_empty())
+        .collect();
+    // Pattern 2 * w is word w, and 2 * w + 1 its reversal.
+    let automaton = Automaton::new(
+        words
+            .iter()
+            .flat_map(|w| [w.clone(), w.iter().rev().copied().collect()]),
+    );
+
+    let height = grid.len() as i32;
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+    let mut matches = Vec::new();
+    for (start, dir) in line_starts(height, width) {
+        let mut state = Automaton::START;
+        let mut pos = start;
+        while (0..height).contains(&pos.0) && (0..width).contains(&pos.1) {
+            match grid[pos.0 as usize].get(pos.1 as usize) {
+                Some(c) => state = automaton.step(state, *c),
+                // Gap in a ragged grid
+                None => state = Automaton::START,
+            }
+            for p in automaton.outputs(state) {
+                let word = &words[p / 2];
+                let reversed = p % 2 == 1;
+                if word.len() == 1 && (reversed || dir != DIRECTIONS[0]) {
+                    continue;
+                }
+                let back = word.len() as i32 - 1;
+                let (match_start, match_dir) = if reversed {
+                    (pos, (-dir.0, -dir.1))
+                } else {
+                    ((pos.0 - dir.0 * back, pos.1 - dir.1 * back), dir)
+                };
+                matches.push((
+                    p / 2,
+                    WordMatch {
+                        start: (match_start.0 as usize, match_start.1 as usize),
+                        direction: match_dir,
+                        word: word.iter().collect(),
+                    },
+                ));
+            }
+            pos = (pos.0 + dir.0, pos.1 + dir.1);
+        }
+    }
+    matches.sort_by_key(|(w, m)| {
+        let d = DIRECTIONS.iter().position(|d| *d == m.direction);
+        (m.start, *w, d)
+    });
+    matches.into_iter().map(|(_, m)| m).collect()
+}
+
+/// Start positions (i, j) and directions of all rows, columns and
+/// diagonals. Together with their reversals, these cover all directions.
+fn line_starts(height: i32, width: i32) -> Vec<((i32, i32), (i32, i32))> {
+    let mut starts = Vec::new();
+    for i in 0..height {
+        starts.push(((i, 0), (0, 1)));
+        starts.push(((i, 0), (1, 1)));
+        starts.push(((i, width - 1), (1, -1)));
+    }
+    for j in 0..width {
+        starts.push(((0, j), (1, 0)));
+        if j > 0 {
+            starts.push(((0, j), (1, 1)));
+        }
+        if j < width - 1 {
+            starts.push(((0, j), (1, -1)));
+        }
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find words as [`find_words_in_lines`], checking every start cell and
+    /// direction.
+    fn find_words<S: AsRef<str>>(grid: &[Vec<char>], words: &[S]) -> Vec<WordMatch> {
+        let words: Vec<Vec<char>> = words
+            .iter()
+            .map(|w| w.as_ref().chars().collect())
+            .filter(|w: &Vec<char>| !w.is_empty())
+            .collect();
+        let mut matches = Vec::new();
+        for i0 in 0..grid.len() {
+            for j0 in 0..grid[i0].len() {
+                for word in &words {
+                    if grid[i0][j0] != word[0] {
+                        continue;
+                    }
+                    let dirs = if word.len() == 1 {
+                        &DIRECTIONS[..1]
+                    } else {
+                        &DIRECTIONS[..]
+                    };
+                    for (di, dj) in dirs {
+                        if word_matches(grid, (i0, j0), (*di, *dj), word) {
+                            matches.push(WordMatch {
+                                start: (i0, j0),
+                                direction: (*di, *dj),
+                                word: word.iter().collect(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    /// Does the word start at `start` in direction `dir`?
+    fn word_matches(
+        grid: &[Vec<char>],
+        start: (usize, usize),
+        dir: (i32, i32),
+        word: &[char],
+    ) -> bool {
+        for (k, mc) in word.iter().enumerate() {
+            let i = (start.0 as i32) + dir.0 * (k as i32);
+            let j = (start.1 as i32) + dir.1 * (k as i32);
+            if i < 0 || j < 0 {
+                return false;
+            }
+            match grid.get(i as usize).and_then(|row| row.get(j as usize)) {
+                Some(c) if c == mc => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn grid(s: &str) -> Vec<Vec<char>> {
+        s.lines().map(|line| line.chars().collect()).collect()
+    }
+
+    #[test]
+    fn match_positions() {
+        let g = grid("..X...\n.SAMX.\n.A..A.\nXMAS.S\n.X....");
+        let matches = find_words_in_lines(&g, &["XMAS"]);
+        let found: Vec<((usize, usize), &str)> = matches
+            .iter()
+            .map(|m| (m.start, direction_name(m.direction)))
+            .collect();
+        assert_eq!(
+            found,
+            vec![((0, 2), "SE"), ((1, 4), "W"), ((3, 0), "E"), ((4, 1), "N")]
+        );
+    }
+
+    #[test]
+    fn multiple_words() {
+        let g = grid("CAT\nOXO\nWOW");
+        let matches = find_words_in_lines(&g, &["CAT", "COW", "TOW", "X", ""]);
+        let words: Vec<&str> = matches.iter().map(|m| m.word.as_str()).collect();
+        assert_eq!(words, vec!["CAT", "COW", "TOW", "X"]);
+        assert_eq!(matches[1].cells(), vec![(0, 0), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn lines_match_naive() {
+        let g = grid(
+            "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX",
+        );
+        let words = ["XMAS", "MAS", "AMA", "SAMX", "XMAS", "M", "MM", ""];
+        let expected = find_words(&g, &words);
+        assert_eq!(expected.iter().filter(|m| m.word == "XMAS").count(), 36);
+        assert_eq!(find_words_in_lines(&g, &words), expected);
+    }
+
+    #[test]
+    fn lines_ragged() {
+        let g = grid("ABC\nA\nABC\nAB");
+        let words = ["ABC", "AA", "CB", "BB"];
+        assert_eq!(find_words_in_lines(&g, &words), find_words(&g, &words));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::graph::{Page, RuleGraph};
+use std::collections::HashMap;
+
+/// Export rules as a Graphviz DOT graph, with edge a -> b for rule `a|b`.
+///
+/// If `update` is given, only its pages and the rules between them are
+/// included, and rules violated by the update are drawn in red.
+pub fn to_dot(graph: &RuleGraph, update: Option<&[Page]>) -> String {
+    let mut result = String::from("digraph rules {\n");
+    match update {
+        None => {
+            for (a, b) in graph.rules() {
+                result.push_str(&format!("    {} -> {};\n", a, b));
+            }
+        }
+        Some(pages) => {
+            let positions: HashMap<Page, usize> =
+                pages.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+            for page in pages {
+                result.push_str(&format!("    {};\n", page));
+            }
+            for page in pages {
+                for next in graph.successors(*page) {
+                    let Some(next_pos) = positions.get(next) else {
+                        continue;
+                    };
+                    if *next_pos < positions[page] {
+                        result.push_str(&format!(
+                            "    {} -> {} [color=red, penwidth=2];\n",
+                            page, next
+                        ));
+                    } else {
+                        result.push_str(&format!("    {} -> {};\n", page, next));
+                    }
+                }
+            }
+        }
+    }
+    result.push_str("}\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> RuleGraph {
+        let mut graph = RuleGraph::default();
+        for (a, b) in [(1, 2), (2, 3), (1, 3), (3, 4)] {
+            graph.add_rule(a, b);
+        }
+        graph
+    }
+
+    #[test]
+    fn full_graph() {
+        assert_eq!(
+            to_dot(&graph(), None),
+            "digraph rules {
+    1 -> 2;
+    1 -> 3;
+    2 -> 3;
+    3 -> 4;
+}
+"
+        );
+    }
+
+    #[test]
+    fn update_subgraph() {
+        assert_eq!(
+            to_dot(&graph(), Some(&[3, 1, 2])),
+            "digraph rules {
+    3;
+    1;
+    2;
+    1 -> 2;
+    1 -> 3 [color=red, penwidth=2];
+    2 -> 3 [color=red, penwidth=2];
+}
+"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
+
+/// Why pages of an update cannot be put in a unique order.
+#[derive(Debug, PartialEq)]
+pub enum OrderError {
+    /// Rules between the pages form a cycle, listed in rule order.
+    Cycle(Vec<Page>),
+    /// No rule orders the two pages, directly or through other pages.
+    Ambiguous(Page, Page),
+    /// Page appears more than once in the update.
+    Repeated(Page),
+}
+
+impl Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderError::Cycle(pages) => {
+                let pages: Vec<String> = pages.iter().map(|p| p.to_string()).collect();
+                write!(
+                    f,
+                    "rules form a cycle {} -> {}",
+                    pages.join(" -> "),
+                    pages[0]
+                )
+            }
+            OrderError::Ambiguous(a, b) => write!(f, "no rule orders pages {} and {}", a, b),
+            OrderError::Repeated(page) => write!(f, "page {} appears more than once", page),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+/// State of a page in depth first search.
+enum Visit {
+    /// Page is on the current search path, at the given position.
+    OnPath(usize),
+    /// All pages reachable from page have been searched.
+    Finished,
+}
+
+/// Page number.
+pub type Page = u64;
+
+/// Page ordering rules, as a directed graph with edge a -> b for rule `a|b`.
+///
+/// Pages are given ids in order of appearance, so memory use is proportional
+/// to the number of rules, regardless of page numbers.
+#[derive(Debug, Default, PartialEq)]
+pub struct RuleGraph {
+    /// Id of each page, which appears before some other page in rules.
+    ids: HashMap<Page, usize>,
+    /// Page of each id.
+    pages: Vec<Page>,
+    /// Pages that must come after page, indexed by page id.
+    successors: Vec<Vec<Page>>,
+}
+
+impl RuleGraph {
+    /// Add rule `before|after`.
+    pub fn add_rule(&mut self, before: Page, after: Page) {
+        let id = *self.ids.entry(before).or_insert_with(|| {
+            self.pages.push(before);
+            self.successors.push(Vec::new());
+            self.pages.len() - 1
+        });
+        if !self.successors[id].contains(&after) {
+            self.successors[id].push(after);
+        }
+    }
+
+    /// Remove rule `before|after`. Returns false if there was no such rule.
+    pub fn remove_rule(&mut self, before: Page, after: Page) -> bool {
+        let Some(id) = self.ids.get(&before) else {
+            return false;
+        };
+        let succ = &mut self.successors[*id];
+        match succ.iter().position(|p| *p == after) {
+            Some(i) => {
+                succ.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Is there rule `before|after`?
+    pub fn has_rule(&self, before: Page, after: Page) -> bool {
+        self.successors(before).contains(&after)
+    }
+
+    /// Pages that must come after `page`, in order of rules.
+    pub fn successors(&self, page: Page) -> &[Page] {
+        self.ids
+            .get(&page)
+            .map_or(&[], |id| self.successors[*id].as_slice())
+    }
+
+    /// All rules (before, after), ordered by first appearance of the earlier
+    /// page.
+    pub fn rules(&self) -> impl Iterator<Item = (Page, Page)> + '_ {
+        self.pages
+            .iter()
+            .zip(&self.successors)
+            .flat_map(|(a, succ)| succ.iter().map(move |b| (*a, *b)))
+    }
+
+    /// Find a cycle in the complete rule set.
+    ///
+    /// Returns pages of some cycle in rule order, or None if rules are acyclic.
+    pub fn find_cycle(&self) -> Option<Vec<Page>> {
+        let pages: HashSet<Page> = self.rules().flat_map(|(a, b)| [a, b]).collect();
+        let mut pages: Vec<Page> = pages.into_iter().collect();
+        pages.sort_unstable();
+        self.find_cycle_in(&pages)
+    }
+
+    /// Find a cycle in the subgraph induced by `pages`, by iterative depth
+    /// first search for a back edge.
+    pub fn find_cycle_in(&self, pages: &[Page]) -> Option<Vec<Page>> {
+        let members: HashSet<Page> = pages.iter().copied().collect();
+        let mut visits = HashMap::new();
+        for start in pages {
+            if visits.contains_key(start) {
+                continue;
+            }
+            // Pages on the current search path, with the number of their
+            // successors searched.
+            let mut path = vec![(*start, 0)];
+            visits.insert(*start, Visit::OnPath(0));
+            while let Some((page, searched)) = path.last_mut() {
+                let Some(next) = self.successors(*page).get(*searched) else {
+                    visits.insert(*page, Visit::Finished);
+                    path.pop();
+                    continue;
+                };
+                *searched += 1;
+                if !members.contains(next) {
+                    continue;
+                }
+                match visits.get(next) {
+                    Some(Visit::OnPath(i)) => {
+                        return Some(path[*i..].iter().map(|(p, _)| *p).collect())
+                    }
+                    Some(Visit::Finished) => {}
+                    None => {
+                        visits.insert(*next, Visit::OnPath(path.len()));
+                        path.push((*next, 0));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Sort pages by rules in the subgraph induced by them, with Kahn's
+    /// algorithm.
+    ///
+    /// Returns error if the rules do not define a unique order, that is, if
+    /// they are cyclic or some pair of pages is not ordered, or if some page
+    /// is repeated.
+    pub fn sort(&self, pages: &[Page]) -> Result<Vec<Page>, OrderError> {
+        let mut seen = HashSet::new();
+        if let Some(page) = pages.iter().find(|p| !seen.insert(**p)) {
+            return Err(OrderError::Repeated(*page));
+        }
+        let mut in_degree: HashMap<Page, usize> = pages.iter().map(|p| (*p, 0)).collect();
+        for page in pages {
+            for next in self.successors(*page) {
+                if let Some(d) = in_degree.get_mut(next) {
+                    *d += 1;
+                }
+            }
+        }
+        let mut ready: VecDeque<Page> = pages
+            .iter()
+            .copied()
+            .filter(|p| in_degree[p] == 0)
+            .collect();
+        let mut sorted = Vec::with_capacity(pages.len());
+        while let Some(page) = ready.pop_front() {
+            if let Some(other) = ready.front() {
+                return Err(OrderError::Ambiguous(page, *other));
+            }
+            sorted.push(page);
+            for next in self.successors(page) {
+                if let Some(d) = in_degree.get_mut(next) {
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push_back(*next);
+                    }
+                }
+            }
+        }
+        if sorted.len() < pages.len() {
+            let remaining: Vec<Page> = pages.iter().copied().filter(|p| in_degree[p] > 0).collect();
+            let cycle = self
+                .find_cycle_in(&remaining)
+                .expect("unsorted pages contain a cycle");
+            return Err(OrderError::Cycle(cycle));
+        }
+        Ok(sorted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(rules: &[(Page, Page)]) -> RuleGraph {
+        let mut graph = RuleGraph::default();
+        for (a, b) in rules {
+            graph.add_rule(*a, *b);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_sort() {
+        let g = graph(&[(1, 2), (2, 3), (1, 3), (7, 1)]);
+        assert_eq!(g.sort(&[3, 1, 2]), Ok(vec![1, 2, 3]));
+        // Rule through page 1 outside the update does not apply
+        assert_eq!(g.sort(&[3, 7]), Err(OrderError::Ambiguous(3, 7)));
+    }
+
+    #[test]
+    fn sort_ambiguous() {
+        let g = graph(&[(1, 2), (1, 3)]);
+        assert_eq!(g.sort(&[3, 2, 1]), Err(OrderError::Ambiguous(2, 3)));
+    }
+
+    #[test]
+    fn sort_cycle() {
+        let g = graph(&[(1, 2), (2, 3), (3, 1), (4, 1)]);
+        assert_eq!(g.sort(&[4, 1, 2, 3]), Err(OrderError::Cycle(vec![1, 2, 3])));
+        // Cycle outside the update does not matter
+        assert_eq!(g.sort(&[4, 2]), Err(OrderError::Ambiguous(4, 2)));
+        assert_eq!(g.sort(&[1, 2]), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn sort_repeated() {
+        let g = graph(&[(1, 2)]);
+        assert_eq!(g.sort(&[2, 1, 2]), Err(OrderError::Repeated(2)));
+        assert_eq!(g.sort(&[3, 3]), Err(OrderError::Repeated(3)));
+    }
+
+    #[test]
+    fn test_remove_rule() {
+        let mut g = graph(&[(1, 2), (1, 3)]);
+        assert!(g.remove_rule(1, 2));
+        assert!(!g.remove_rule(1, 2));
+        assert!(!g.remove_rule(8, 9));
+        assert_eq!(g.successors(1), &[3]);
+    }
+
+    #[test]
+    fn sparse_pages() {
+        let g = graph(&[(5, 999_999_999_999), (u64::MAX, 5)]);
+        assert_eq!(g.successors.len(), 2);
+        assert!(g.has_rule(5, 999_999_999_999));
+        assert_eq!(
+            g.sort(&[999_999_999_999, 5, u64::MAX]),
+            Ok(vec![u64::MAX, 5, 999_999_999_999])
+        );
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        assert_eq!(graph(&[(1, 2), (2, 3), (1, 3)]).find_cycle(), None);
+        assert_eq!(
+            graph(&[(5, 1), (1, 2), (2, 3), (3, 1)]).find_cycle(),
+            Some(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn find_cycle_long_chain() {
+        let n = 200_000;
+        let mut g = graph(&(0..n).map(|p| (p, p + 1)).collect::<Vec<_>>());
+        assert_eq!(g.find_cycle(), None);
+        g.add_rule(n, n - 2);
+        assert_eq!(g.find_cycle(), Some(vec![n - 2, n - 1, n]));
+    }
+}
@@ -1,42 +1,94 @@
+use graph::{OrderError, Page, RuleGraph};
+use queue::{PrintQueue, Status};
 use std::{
-    cmp::Ordering,
+    env,
+    fmt::Display,
     io::{self, Read},
 };
 
+mod dot;
+mod graph;
+mod queue;
+mod repair;
+
 /// Day 5: Print Queue
 ///
+/// Run with argument `check` to look for cycles in the complete rule set.
+///
+/// Run with argument `report` to list incorrect updates with the rules they
+/// violate, their corrected order and the fewest page moves to correct them.
+///
+/// Run with arguments `queue <file>` to load rules and updates to a
+/// [`PrintQueue`](queue::PrintQueue), and apply edits from the file, one per
+/// line: `+a|b` or `-a|b` to add or remove a rule, `+p1,p2,...` to add an
+/// update and `-n` to remove update number `n`. Sums of both parts are
+/// printed after each edit.
+///
+/// Run with arguments `dot [n]` to export rules as a Graphviz DOT graph. If
+/// update number `n` (starting from 1) is given, only rules between its pages
+/// are included, and violated rules are highlighted.
+///
 /// <https://adventofcode.com/2024/day/5>
 fn main() -> io::Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
+    if env::args().nth(1).as_deref() == Some("check") {
+        let (rules, _) = input.split_once("\n\n").unwrap_or((&input, ""));
+        match build_graph(rules).find_cycle() {
+            Some(cycle) => println!("Rules are cyclic: {}", OrderError::Cycle(cycle)),
+            None => println!("Rules are acyclic"),
+        }
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("report") {
+        let (rules, updates) = input.split_once("\n\n").unwrap();
+        print!("{}", report(&build_graph(rules), updates));
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("queue") {
+        let path = env::args()
+            .nth(2)
+            .ok_or_else(|| io::Error::other("queue requires a file"))?;
+        let edits = std::fs::read_to_string(path)?;
+        return run_queue(&input, &edits).map_err(io::Error::other);
+    }
+    if env::args().nth(1).as_deref() == Some("dot") {
+        let (rules, updates) = input.split_once("\n\n").unwrap_or((&input, ""));
+        let update = match env::args().nth(2) {
+            Some(n) => {
+                let n: usize = n.parse().map_err(io::Error::other)?;
+                let pages = n
+                    .checked_sub(1)
+                    .and_then(|i| parse_updates(updates).nth(i))
+                    .ok_or_else(|| io::Error::other(format!("no update {}", n)))?;
+                Some(pages)
+            }
+            None => None,
+        };
+        print!("{}", dot::to_dot(&build_graph(rules), update.as_deref()));
+        return Ok(());
+    }
     println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    println!("Part 2: {}", part2(&input).map_err(io::Error::other)?);
     Ok(())
 }
 
 /// Based on page ordering rules, find out which updates are correct.
 ///
 /// Return sum of middle numbers in correct updates.
-fn part1(input: &str) -> usize {
+fn part1(input: &str) -> Sum {
     let (rules, updates) = input.split_once("\n\n").unwrap();
     let graph = build_graph(rules);
 
-    updates
-        .lines()
-        .map(|line| {
-            line.split(',')
-                .filter_map(|s| s.parse::<usize>().ok())
-                .collect::<Vec<usize>>()
-        })
+    parse_updates(updates)
         .filter(|pages| update_is_correct(&graph, pages))
-        .map(|pages| pages[(pages.len()).div_ceil(2) - 1])
+        .map(|pages| middle(&pages) as Sum)
         .sum()
 }
 
-/// Adjacency list representation of graph
-fn build_graph(rules: &str) -> Vec<Vec<bool>> {
-    let mut graph = Vec::with_capacity(100);
-    let mut max_n = 0;
+/// Parse rules `a|b`, one per line, into a graph.
+fn build_graph(rules: &str) -> RuleGraph {
+    let mut graph = RuleGraph::default();
     for (i, j) in rules.lines().filter_map(|line| {
         line.split_once("|").and_then(|(a, b)| {
             if let (Ok(a), Ok(b)) = (a.parse(), b.parse()) {
@@ -46,79 +98,204 @@ fn build_graph(rules: &str) -> Vec<Vec<bool>> {
             }
         })
     }) {
-        while graph.len() <= i {
-            graph.push(Vec::with_capacity(100));
-        }
-        while graph[i].len() <= j {
-            graph[i].push(false);
-        }
-        graph[i][j] = true;
-
-        if j > max_n {
-            max_n = j;
-        }
+        graph.add_rule(i, j);
     }
-    if graph.len() > max_n {
-        max_n = graph.len()
+    graph
+}
+
+/// Sum of middle pages, wide enough for any number of `u64` pages.
+type Sum = u128;
+
+/// Middle page of update, or 0 for an empty update.
+fn middle(pages: &[Page]) -> Page {
+    pages
+        .get(pages.len().div_ceil(2).saturating_sub(1))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Parse updates, one per line, to lists of page numbers.
+fn parse_updates(updates: &str) -> impl Iterator<Item = Vec<Page>> + '_ {
+    updates.lines().map(|line| {
+        line.split(',')
+            .filter_map(|s| s.parse::<Page>().ok())
+            .collect::<Vec<Page>>()
+    })
+}
+
+/// Rule `before|after` violated by an update, where page `after` is at
+/// position `after_pos`, preceding page `before` at `before_pos`.
+#[derive(Debug, PartialEq)]
+struct Violation {
+    before: Page,
+    after: Page,
+    before_pos: usize,
+    after_pos: usize,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}|{}: {} at position {} is before {} at position {}",
+            self.before, self.after, self.after, self.after_pos, self.before, self.before_pos
+        )
     }
+}
 
-    // Fill rest of graph up to length
-    while graph.len() < max_n {
-        graph.push(Vec::with_capacity(max_n));
+/// Find all rules violated by the update.
+///
+/// Returns violations ordered by position of the earlier page.
+fn violations(graph: &RuleGraph, pages: &[Page]) -> Vec<Violation> {
+    let mut result = Vec::new();
+    for (p_i, i) in pages.iter().enumerate() {
+        for (p_j, j) in pages.iter().enumerate().skip(p_i + 1) {
+            if graph.has_rule(*j, *i) {
+                result.push(Violation {
+                    before: *j,
+                    after: *i,
+                    before_pos: p_j,
+                    after_pos: p_i,
+                });
+            }
+        }
     }
-    for row in graph.iter_mut() {
-        while row.len() <= max_n {
-            row.push(false);
+    result
+}
+
+/// Check if update is correct, that is, no rule is violated.
+fn update_is_correct(graph: &RuleGraph, pages: &[Page]) -> bool {
+    violations(graph, pages).is_empty()
+}
+
+/// List incorrect updates with their violations, corrected order and
+/// minimal moves to correct them.
+fn report(graph: &RuleGraph, updates: &str) -> String {
+    let mut result = String::new();
+    for (n, pages) in parse_updates(updates).enumerate() {
+        let violations = violations(graph, &pages);
+        if violations.is_empty() {
+            continue;
+        }
+        result.push_str(&format!("Update {}: {}\n", n + 1, join(&pages)));
+        for violation in violations {
+            result.push_str(&format!("  violates {}\n", violation));
+        }
+        match sort_update(graph, pages.clone()) {
+            Ok(sorted) => result.push_str(&format!("  corrected: {}\n", join(&sorted))),
+            Err(e) => result.push_str(&format!("  cannot correct: {}\n", e)),
+        }
+        match repair::repair(graph, &pages) {
+            Ok(moves) => {
+                let moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+                result.push_str(&format!(
+                    "  moves ({}): {}\n",
+                    moves.len(),
+                    moves.join(", ")
+                ));
+            }
+            Err(e) => result.push_str(&format!("  cannot repair: {}\n", e)),
         }
     }
-    graph
+    result
 }
 
-/// Check if update is correct.
-fn update_is_correct(graph: &[Vec<bool>], pages: &[usize]) -> bool {
-    for (p_i, i) in pages.iter().enumerate() {
-        for j in pages[p_i + 1..].iter() {
-            if !graph[*i][*j] {
-                return false;
+/// Load input into a print queue and apply edits, printing sums after each.
+fn run_queue(input: &str, edits: &str) -> Result<(), String> {
+    let (rules, updates) = input.split_once("\n\n").unwrap_or((input, ""));
+    let mut queue = PrintQueue::default();
+    for (a, b) in build_graph(rules).rules() {
+        queue.add_rule(a, b);
+    }
+    for pages in parse_updates(updates) {
+        queue.add_update(pages);
+    }
+    println!("Part 1: {}, Part 2: {}", queue.part1(), queue.part2());
+    for edit in edits.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let (add, item) = match (edit.strip_prefix('+'), edit.strip_prefix('-')) {
+            (Some(item), _) => (true, item),
+            (_, Some(item)) => (false, item),
+            _ => return Err(format!("edit must start with + or -: {}", edit)),
+        };
+        let invalid = || format!("invalid edit: {}", edit);
+        let mut status = String::new();
+        if let Some((a, b)) = item.split_once('|') {
+            let (a, b) = (
+                a.parse().map_err(|_| invalid())?,
+                b.parse().map_err(|_| invalid())?,
+            );
+            if add {
+                queue.add_rule(a, b);
+            } else if !queue.remove_rule(a, b) {
+                return Err(format!("no rule {}", item));
             }
+        } else if add {
+            let pages = item
+                .split(',')
+                .map(|p| p.parse())
+                .collect::<Result<Vec<Page>, _>>()
+                .map_err(|_| invalid())?;
+            let id = queue.add_update(pages);
+            status = match queue.status(id) {
+                Some(Status::Correct) => format!("update {} is correct", id + 1),
+                Some(Status::Corrected(sorted)) => {
+                    format!("update {} corrects to {}", id + 1, join(sorted))
+                }
+                Some(Status::Unsortable(e)) => {
+                    format!("update {} cannot be corrected: {}", id + 1, e)
+                }
+                None => unreachable!("update was added"),
+            };
+        } else {
+            let n: usize = item.parse().map_err(|_| invalid())?;
+            n.checked_sub(1)
+                .and_then(|id| queue.remove_update(id))
+                .ok_or_else(|| format!("no update {}", n))?;
+        }
+        println!(
+            "{}: Part 1: {}, Part 2: {}",
+            edit,
+            queue.part1(),
+            queue.part2()
+        );
+        if !status.is_empty() {
+            println!("  {}", status);
         }
     }
-    true
+    Ok(())
+}
+
+/// Format pages as in input, separated by commas.
+fn join(pages: &[Page]) -> String {
+    pages
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
 }
 
 /// Based on page ordering rules, sort incorrect updates.
 ///
 /// Return sum of middle numbers in corrected (previously incorrect) updates.
-fn part2(input: &str) -> usize {
+fn part2(input: &str) -> Result<Sum, OrderError> {
     let (rules, updates) = input.split_once("\n\n").unwrap();
     let graph = build_graph(rules);
 
-    updates
-        .lines()
-        .map(|line| {
-            line.split(',')
-                .filter_map(|s| s.parse::<usize>().ok())
-                .collect::<Vec<usize>>()
-        })
-        .filter(|pages| !update_is_correct(&graph, pages))
-        .map(|pages| sort_update(&graph, pages))
-        .map(|pages| pages[(pages.len()).div_ceil(2) - 1])
-        .sum()
+    let mut result = 0;
+    for pages in parse_updates(updates).filter(|pages| !update_is_correct(&graph, pages)) {
+        let pages = sort_update(&graph, pages)?;
+        result += middle(&pages) as Sum;
+    }
+    Ok(result)
 }
 
 /// Sort vector in the order specified in graph.
-fn sort_update(graph: &[Vec<bool>], mut pages: Vec<usize>) -> Vec<usize> {
-    pages.sort_by(|a, b| match graph[*a][*b] {
-        true => Ordering::Less,
-        false => Ordering::Greater,
-    });
-    pages
+fn sort_update(graph: &RuleGraph, pages: Vec<Page>) -> Result<Vec<Page>, OrderError> {
+    graph.sort(&pages)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::build_graph;
-
     use super::*;
 
     static SAMPLE: &str = "47|53
@@ -161,17 +338,20 @@ mod tests {
 1|2
 2|3";
         let received = build_graph(rules);
-        let expected = vec![
-            vec![false, false, false, false],
-            vec![false, false, true, true],
-            vec![false, false, false, true],
-        ];
-        assert_eq!(received, expected);
+        assert_eq!(received.successors(0), &[] as &[Page]);
+        assert_eq!(received.successors(1), &[3, 2]);
+        assert_eq!(received.successors(2), &[3]);
+        assert_eq!(received.successors(3), &[] as &[Page]);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(SAMPLE), 123);
+        assert_eq!(part2(SAMPLE), Ok(123));
+    }
+
+    #[test]
+    fn part2_repeated_page() {
+        assert_eq!(part2("1|2\n\n2,1,2"), Err(OrderError::Repeated(2)));
     }
 
     #[test]
@@ -180,6 +360,85 @@ mod tests {
         let graph = build_graph(rules);
 
         let received = sort_update(&graph, vec![75, 97, 47, 61, 53]);
-        assert_eq!(received, vec![97, 75, 47, 61, 53]);
+        assert_eq!(received, Ok(vec![97, 75, 47, 61, 53]));
+    }
+
+    #[test]
+    fn test_violations() {
+        let (rules, _) = SAMPLE.split_once("\n\n").unwrap();
+        let graph = build_graph(rules);
+
+        assert_eq!(violations(&graph, &[75, 47, 61, 53, 29]), vec![]);
+        assert_eq!(
+            violations(&graph, &[61, 13, 29]),
+            vec![Violation {
+                before: 29,
+                after: 13,
+                before_pos: 2,
+                after_pos: 1
+            }]
+        );
+        let received = violations(&graph, &[97, 13, 75, 29, 47]);
+        let rules: Vec<(Page, Page)> = received.iter().map(|v| (v.before, v.after)).collect();
+        assert_eq!(rules, vec![(75, 13), (29, 13), (47, 13), (47, 29)]);
+    }
+
+    #[test]
+    fn test_report() {
+        let (rules, updates) = SAMPLE.split_once("\n\n").unwrap();
+        let received = report(&build_graph(rules), updates);
+        assert!(received.starts_with(
+            "Update 4: 75,97,47,61,53
+  violates 97|75: 75 at position 0 is before 97 at position 1
+  corrected: 97,75,47,61,53
+  moves (1): 75 from 0 to 1
+Update 5:"
+        ));
+        assert_eq!(received.matches("Update").count(), 3);
+    }
+
+    #[test]
+    fn report_ambiguous() {
+        let graph = build_graph("1|2\n1|3");
+        assert_eq!(
+            report(&graph, "3,2,1"),
+            "Update 1: 3,2,1
+  violates 1|3: 3 at position 0 is before 1 at position 2
+  violates 1|2: 2 at position 1 is before 1 at position 2
+  cannot correct: no rule orders pages 2 and 3
+  moves (1): 1 from 2 to 0
+"
+        );
+    }
+
+    /// Sums of sparse `u64` pages do not overflow.
+    #[test]
+    fn sparse_page_sums() {
+        let max = u64::MAX as Sum;
+        let input = "18446744073709551615|1\n\n18446744073709551615\n18446744073709551615\n1,18446744073709551615\n1,18446744073709551615";
+        assert_eq!(part1(input), 2 * max);
+        assert_eq!(part2(input), Ok(2 * max));
+
+        let mut queue = PrintQueue::default();
+        queue.add_rule(u64::MAX, 1);
+        for _ in 0..3 {
+            queue.add_update(vec![1, u64::MAX]);
+            queue.add_update(vec![u64::MAX]);
+        }
+        assert_eq!((queue.part1(), queue.part2()), (3 * max, 3 * max));
+    }
+
+    #[test]
+    fn print_queue_matches_parts() {
+        let (rules, updates) = SAMPLE.split_once("\n\n").unwrap();
+        let mut queue = PrintQueue::default();
+        for pages in parse_updates(updates) {
+            queue.add_update(pages);
+        }
+        for (a, b) in build_graph(rules).rules() {
+            queue.add_rule(a, b);
+        }
+        assert_eq!(queue.part1(), part1(SAMPLE));
+        assert_eq!(Ok(queue.part2()), part2(SAMPLE));
     }
 }
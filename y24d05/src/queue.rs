@@ -0,0 +1,192 @@
+use crate::{
+    graph::{OrderError, Page, RuleGraph},
+    middle, sort_update, update_is_correct, Sum,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Validation state of an update.
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    /// Update follows the rules.
+    Correct,
+    /// Update violates rules, and sorts to the given order.
+    Corrected(Vec<Page>),
+    /// Update violates rules, which do not define a unique order.
+    Unsortable(OrderError),
+}
+
+struct Entry {
+    pages: Vec<Page>,
+    status: Status,
+}
+
+/// Rules and updates, which are kept validated as they change.
+///
+/// Changing a rule re-validates only updates containing both of its pages,
+/// and sums of part 1 and part 2 are kept current.
+#[derive(Default)]
+pub struct PrintQueue {
+    graph: RuleGraph,
+    /// Updates by id. Ids of removed updates are not reused.
+    updates: Vec<Option<Entry>>,
+    /// Ids of updates containing each page.
+    by_page: HashMap<Page, HashSet<usize>>,
+    correct_sum: Sum,
+    corrected_sum: Sum,
+}
+
+impl PrintQueue {
+    /// Sum of middle pages of correct updates.
+    pub fn part1(&self) -> Sum {
+        self.correct_sum
+    }
+
+    /// Sum of middle pages of corrected updates.
+    pub fn part2(&self) -> Sum {
+        self.corrected_sum
+    }
+
+    pub fn status(&self, id: usize) -> Option<&Status> {
+        self.entry(id).map(|e| &e.status)
+    }
+
+    fn entry(&self, id: usize) -> Option<&Entry> {
+        self.updates.get(id).and_then(|e| e.as_ref())
+    }
+
+    /// Add rule `before|after`, re-validating affected updates.
+    pub fn add_rule(&mut self, before: Page, after: Page) {
+        if !self.graph.has_rule(before, after) {
+            self.graph.add_rule(before, after);
+            self.revalidate(before, after);
+        }
+    }
+
+    /// Remove rule `before|after`, re-validating affected updates.
+    ///
+    /// Returns false if there was no such rule.
+    pub fn remove_rule(&mut self, before: Page, after: Page) -> bool {
+        let removed = self.graph.remove_rule(before, after);
+        if removed {
+            self.revalidate(before, after);
+        }
+        removed
+    }
+
+    /// Add update, returning its id.
+    pub fn add_update(&mut self, pages: Vec<Page>) -> usize {
+        let id = self.updates.len();
+        for page in &pages {
+            self.by_page.entry(*page).or_default().insert(id);
+        }
+        let status = self.validate(&pages);
+        self.add_to_sums(&pages, &status);
+        self.updates.push(Some(Entry { pages, status }));
+        id
+    }
+
+    /// Remove update, returning its pages.
+    pub fn remove_update(&mut self, id: usize) -> Option<Vec<Page>> {
+        let entry = self.updates.get_mut(id)?.take()?;
+        for page in &entry.pages {
+            if let Some(ids) = self.by_page.get_mut(page) {
+                ids.remove(&id);
+            }
+        }
+        self.remove_from_sums(&entry.pages, &entry.status);
+        Some(entry.pages)
+    }
+
+    /// Re-validate updates containing both pages of a changed rule.
+    fn revalidate(&mut self, a: Page, b: Page) {
+        let (Some(with_a), Some(with_b)) = (self.by_page.get(&a), self.by_page.get(&b)) else {
+            return;
+        };
+        let affected: Vec<usize> = with_a.intersection(with_b).copied().collect();
+        for id in affected {
+            let entry = self.updates[id].take().expect("indexed update exists");
+            self.remove_from_sums(&entry.pages, &entry.status);
+            let status = self.validate(&entry.pages);
+            self.add_to_sums(&entry.pages, &status);
+            self.updates[id] = Some(Entry {
+                pages: entry.pages,
+                status,
+            });
+        }
+    }
+
+    fn validate(&self, pages: &[Page]) -> Status {
+        if update_is_correct(&self.graph, pages) {
+            return Status::Correct;
+        }
+        match sort_update(&self.graph, pages.to_vec()) {
+            Ok(sorted) => Status::Corrected(sorted),
+            Err(e) => Status::Unsortable(e),
+        }
+    }
+
+    fn add_to_sums(&mut self, pages: &[Page], status: &Status) {
+        match status {
+            Status::Correct => self.correct_sum += middle(pages) as Sum,
+            Status::Corrected(sorted) => self.corrected_sum += middle(sorted) as Sum,
+            Status::Unsortable(_) => {}
+        }
+    }
+
+    fn remove_from_sums(&mut self, pages: &[Page], status: &Status) {
+        match status {
+            Status::Correct => self.correct_sum -= middle(pages) as Sum,
+            Status::Corrected(sorted) => self.corrected_sum -= middle(sorted) as Sum,
+            Status::Unsortable(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incremental_rules() {
+        let mut queue = PrintQueue::default();
+        let id = queue.add_update(vec![3, 1, 2]);
+        let other = queue.add_update(vec![5, 6]);
+        assert_eq!(queue.status(id), Some(&Status::Correct));
+        assert_eq!(queue.part1(), 1 + 5);
+
+        queue.add_rule(1, 3);
+        queue.add_rule(1, 2);
+        assert_eq!(
+            queue.status(id),
+            Some(&Status::Unsortable(OrderError::Ambiguous(3, 2)))
+        );
+        assert_eq!((queue.part1(), queue.part2()), (5, 0));
+
+        queue.add_rule(2, 3);
+        assert_eq!(queue.status(id), Some(&Status::Corrected(vec![1, 2, 3])));
+        assert_eq!((queue.part1(), queue.part2()), (5, 2));
+
+        queue.add_rule(6, 5);
+        assert_eq!((queue.part1(), queue.part2()), (0, 2 + 6));
+
+        assert!(queue.remove_rule(1, 3));
+        assert_eq!(queue.status(id), Some(&Status::Corrected(vec![1, 2, 3])));
+        assert!(!queue.remove_rule(1, 3));
+        assert_eq!(queue.remove_update(other), Some(vec![5, 6]));
+        assert_eq!(queue.remove_update(other), None);
+        assert_eq!(queue.status(other), None);
+        assert_eq!((queue.part1(), queue.part2()), (0, 2));
+    }
+
+    #[test]
+    fn repeated_page() {
+        let mut queue = PrintQueue::default();
+        queue.add_rule(1, 2);
+        let id = queue.add_update(vec![2, 1, 2]);
+        assert_eq!(
+            queue.status(id),
+            Some(&Status::Unsortable(OrderError::Repeated(2)))
+        );
+        assert_eq!((queue.part1(), queue.part2()), (0, 0));
+    }
+}
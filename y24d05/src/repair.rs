@@ -0,0 +1,296 @@
+use crate::graph::{OrderError, Page, RuleGraph};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::Display,
+};
+
+/// Move of a page within an update.
+#[derive(Debug, PartialEq)]
+pub struct Move {
+    pub page: Page,
+    /// Position of the page before the move.
+    pub from: usize,
+    /// Position of the page after the move.
+    pub to: usize,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} from {} to {}", self.page, self.from, self.to)
+    }
+}
+
+/// Find the fewest page moves that put the update in rule order.
+///
+/// Rules apply between pages of the update, directly or through other pages
+/// of the update, and need not order every pair of pages. The largest set of
+/// pages, no two of which break a rule, stay in place, and every other page
+/// is moved once to a position consistent with the rules. Moves are applied
+/// one after another, and positions of each move refer to the update as left
+/// by the previous moves.
+///
+/// Returns error if rules between the pages form a cycle, or if some page is
+/// repeated.
+pub fn repair(graph: &RuleGraph, pages: &[Page]) -> Result<Vec<Move>, OrderError> {
+    let mut seen = HashSet::new();
+    if let Some(page) = pages.iter().find(|p| !seen.insert(**p)) {
+        return Err(OrderError::Repeated(*page));
+    }
+    if let Some(cycle) = graph.find_cycle_in(pages) {
+        return Err(OrderError::Cycle(cycle));
+    }
+    let before = closure(graph, pages);
+    let mut placed = largest_consistent(&before);
+    let order = extension(&before, &placed);
+
+    let mut current = pages.to_vec();
+    let mut moves = Vec::new();
+    for (r, &i) in order.iter().enumerate() {
+        if placed[i] {
+            continue;
+        }
+        let page = pages[i];
+        let from = current.iter().position(|p| *p == page).unwrap();
+        current.remove(from);
+        // Insert after the closest placed predecessor, keeping placed pages
+        // in order.
+        let to = match order[..r].iter().rev().find(|q| placed[**q]) {
+            Some(q) => current.iter().position(|p| *p == pages[*q]).unwrap() + 1,
+            None => 0,
+        };
+        current.insert(to, page);
+        placed[i] = true;
+        moves.push(Move { page, from, to });
+    }
+    Ok(moves)
+}
+
+/// Which pages must come before which, by positions in the update.
+///
+/// `before[i][j]` is true if rules lead from page i to page j through pages
+/// of the update.
+fn closure(graph: &RuleGraph, pages: &[Page]) -> Vec<Vec<bool>> {
+    let index: HashMap<Page, usize> = pages.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+    let mut before = vec![vec![false; pages.len()]; pages.len()];
+    for (i, row) in before.iter_mut().enumerate() {
+        let mut stack = vec![pages[i]];
+        while let Some(page) = stack.pop() {
+            for next in graph.successors(page) {
+                if let Some(&j) = index.get(next) {
+                    if !row[j] {
+                        row[j] = true;
+                        stack.push(*next);
+                    }
+                }
+            }
+        }
+    }
+    before
+}
+
+/// Largest set of positions, no two of which are in the wrong order.
+///
+/// Pairs in the wrong order form a partial order, so the set is a maximum
+/// antichain of it, found from a maximum matching by König's theorem.
+fn largest_consistent(before: &[Vec<bool>]) -> Vec<bool> {
+    let n = before.len();
+    let wrong = |i: usize, j: usize| i < j && before[j][i];
+    // Left vertex matched to each right vertex.
+    let mut matched: Vec<Option<usize>> = vec![None; n];
+    for i in 0..n {
+        augment(i, &wrong, &mut vec![false; n], &mut matched);
+    }
+    // Vertices on alternating paths from unmatched left vertices.
+    let mut left = vec![false; n];
+    let mut right = vec![false; n];
+    let mut stack: Vec<usize> = (0..n).filter(|i| !matched.contains(&Some(*i))).collect();
+    while let Some(i) = stack.pop() {
+        if left[i] {
+            continue;
+        }
+        left[i] = true;
+        for j in 0..n {
+            if wrong(i, j) && !right[j] {
+                right[j] = true;
+                stack.extend(matched[j]);
+            }
+        }
+    }
+    (0..n).map(|x| left[x] && !right[x]).collect()
+}
+
+/// Find augmenting path from left vertex `i` with Kuhn's algorithm.
+fn augment(
+    i: usize,
+    edge: &dyn Fn(usize, usize) -> bool,
+    seen: &mut [bool],
+    matched: &mut [Option<usize>],
+) -> bool {
+    for j in 0..matched.len() {
+        if !edge(i, j) || seen[j] {
+            continue;
+        }
+        seen[j] = true;
+        if matched[j].is_none_or(|k| augment(k, edge, seen, matched)) {
+            matched[j] = Some(i);
+            return true;
+        }
+    }
+    false
+}
+
+/// Order of positions consistent with the rules, keeping `placed` positions
+/// in their current order and otherwise preferring earlier positions.
+fn extension(before: &[Vec<bool>], placed: &[bool]) -> Vec<usize> {
+    let n = before.len();
+    let kept: Vec<usize> = (0..n).filter(|i| placed[*i]).collect();
+    let mut edges = before.to_vec();
+    for pair in kept.windows(2) {
+        edges[pair[0]][pair[1]] = true;
+    }
+    let mut in_degree: Vec<usize> = (0..n)
+        .map(|j| (0..n).filter(|i| edges[*i][j]).count())
+        .collect();
+    let mut ready: BinaryHeap<Reverse<usize>> =
+        (0..n).filter(|i| in_degree[*i] == 0).map(Reverse).collect();
+    let mut order = Vec::with_capacity(n);
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(i);
+        for j in 0..n {
+            if edges[i][j] {
+                in_degree[j] -= 1;
+                if in_degree[j] == 0 {
+                    ready.push(Reverse(j));
+                }
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply moves to update, one after another.
+    fn apply(pages: &[Page], moves: &[Move]) -> Vec<Page> {
+        let mut pages = pages.to_vec();
+        for m in moves {
+            let page = pages.remove(m.from);
+            pages.insert(m.to, page);
+        }
+        pages
+    }
+
+    /// Graph with rules ordering pages 0..n ascending.
+    fn ascending(n: Page) -> RuleGraph {
+        let mut graph = RuleGraph::default();
+        for a in 0..n {
+            for b in a + 1..n {
+                graph.add_rule(a, b);
+            }
+        }
+        graph
+    }
+
+    /// Fewest moves by trying every set of pages to keep.
+    fn fewest_moves(graph: &RuleGraph, pages: &[Page]) -> usize {
+        let before = closure(graph, pages);
+        let n = pages.len();
+        (0..1_u32 << n)
+            .filter(|keep| {
+                (0..n).all(|i| {
+                    (i + 1..n)
+                        .all(|j| keep & (1 << i) == 0 || keep & (1 << j) == 0 || !before[j][i])
+                })
+            })
+            .map(|keep| n - keep.count_ones() as usize)
+            .min()
+            .unwrap()
+    }
+
+    /// Update is in order, where rules apply through its pages.
+    fn in_order(graph: &RuleGraph, pages: &[Page]) -> bool {
+        let before = closure(graph, pages);
+        (0..pages.len()).all(|i| (0..i).all(|j| !before[i][j]))
+    }
+
+    #[test]
+    fn single_move() {
+        let graph = ascending(6);
+        let moves = repair(&graph, &[1, 2, 3, 4, 5, 0]).unwrap();
+        assert_eq!(
+            moves,
+            vec![Move {
+                page: 0,
+                from: 5,
+                to: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn moves_sort_update() {
+        let graph = ascending(8);
+        for pages in [
+            vec![7, 6, 5, 4, 3, 2, 1, 0],
+            vec![2, 0, 5, 1, 7, 3, 6, 4],
+            vec![0, 1, 2],
+        ] {
+            let moves = repair(&graph, &pages).unwrap();
+            let mut sorted = pages.clone();
+            sorted.sort();
+            assert_eq!(apply(&pages, &moves), sorted);
+            assert_eq!(moves.len(), fewest_moves(&graph, &pages));
+        }
+    }
+
+    #[test]
+    fn partial_order() {
+        let mut graph = RuleGraph::default();
+        graph.add_rule(1, 2);
+        let moves = repair(&graph, &[2, 1, 3]).unwrap();
+        assert_eq!(
+            moves,
+            vec![Move {
+                page: 2,
+                from: 0,
+                to: 1
+            }]
+        );
+
+        // Rules through page 5 order 4 before 6, rules through 9 do not apply.
+        for (a, b) in [(4, 5), (5, 6), (7, 8), (8, 9), (9, 3), (6, 3)] {
+            graph.add_rule(a, b);
+        }
+        for pages in [
+            vec![6, 2, 4, 1, 5],
+            vec![3, 8, 6, 7, 2, 5, 1, 4],
+            vec![3, 6, 5, 4, 8, 7],
+        ] {
+            let moves = repair(&graph, &pages).unwrap();
+            let repaired = apply(&pages, &moves);
+            assert!(in_order(&graph, &repaired), "{:?}", repaired);
+            assert_eq!(moves.len(), fewest_moves(&graph, &pages), "{:?}", pages);
+        }
+    }
+
+    #[test]
+    fn repeated() {
+        assert_eq!(
+            repair(&ascending(3), &[2, 1, 2]),
+            Err(OrderError::Repeated(2))
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let mut graph = ascending(3);
+        graph.add_rule(2, 0);
+        assert_eq!(
+            repair(&graph, &[1, 0, 2]),
+            Err(OrderError::Cycle(vec![1, 2, 0]))
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    sim::{Event, GuardSim, DIRS},
+    states::StateGrid,
+};
+
+/// Precomputed guard moves between obstacles.
+pub struct JumpTable {
+    shape: (usize, usize),
+    /// Square in front of the next obstacle, indexed by [dir_i][i * width + j],
+    /// or None if the guard walks off the map.
+    stops: [Vec<Option<(usize, usize)>>; 4],
+}
+
+impl JumpTable {
+    /// Build tables for room map with obstacles `#`.
+    pub fn new(grid: &[Vec<char>]) -> Self {
+        let shape = (grid.len(), grid[0].len());
+        let mut stops: [Vec<Option<(usize, usize)>>; 4] =
+            std::array::from_fn(|_| vec![None; shape.0 * shape.1]);
+        for (dir_i, &(di, dj)) in DIRS.iter().enumerate() {
+            // Visit squares ahead before the squares behind them.
+            let rows: Vec<usize> = if di > 0 {
+                (0..shape.0).rev().collect()
+            } else {
+                (0..shape.0).collect()
+            };
+            let cols: Vec<usize> = if dj > 0 {
+                (0..shape.1).rev().collect()
+            } else {
+                (0..shape.1).collect()
+            };
+            for &i in &rows {
+                for &j in &cols {
+                    let ahead = (i as i32 + di, j as i32 + dj);
+                    if (ahead.0 < 0)
+                        || (ahead.0 >= shape.0 as i32)
+                        || (ahead.1 < 0)
+                        || (ahead.1 >= shape.1 as i32)
+                    {
+                        continue;
+                    }
+                    let ahead = (ahead.0 as usize, ahead.1 as usize);
+                    stops[dir_i][i * shape.1 + j] = if grid[ahead.0][ahead.1] == '#' {
+                        Some((i, j))
+                    } else {
+                        stops[dir_i][ahead.0 * shape.1 + ahead.1]
+                    };
+                }
+            }
+        }
+        JumpTable { shape, stops }
+    }
+
+    /// Square where the guard stops walking from `pos` towards `dir_i`, or
+    /// None if it walks off the map.
+    ///
+    /// An additional obstacle `block` is taken into account without
+    /// rebuilding the tables.
+    pub fn jump(
+        &self,
+        pos: (usize, usize),
+        dir_i: usize,
+        block: Option<(usize, usize)>,
+    ) -> Option<(usize, usize)> {
+        let stop = self.stops[dir_i][pos.0 * self.shape.1 + pos.1];
+        let Some(block) = block else {
+            return stop;
+        };
+        let (di, dj) = DIRS[dir_i];
+        let on_ray = if di == 0 {
+            block.0 == pos.0
+        } else {
+            block.1 == pos.1
+        };
+        // Distance from `pos` along the direction.
+        let along =
+            |p: (usize, usize)| (p.0 as i32 - pos.0 as i32) * di + (p.1 as i32 - pos.1 as i32) * dj;
+        let dist = along(block);
+        if on_ray && dist > 0 && dist <= stop.map_or(i32::MAX, along) {
+            Some((
+                (block.0 as i32 - di) as usize,
+                (block.1 as i32 - dj) as usize,
+            ))
+        } else {
+            stop
+        }
+    }
+}
+
+/// Guard position and direction.
+pub type State = ((usize, usize), usize);
+
+/// Obstruction, which makes the guard walk in a loop.
+#[derive(Debug, PartialEq)]
+pub struct LoopBlock {
+    pub block: (usize, usize),
+    /// States of the loop, each one move or turn after the previous. The
+    /// guard returns to the first state after the last one.
+    pub cycle: Vec<State>,
+}
+
+/// Find obstruction positions, which make the guard walk in a loop.
+///
+/// Each candidate on the guard path is checked starting from the square just
+/// before the guard would first walk into it, jumping between obstacles.
+///
+/// Returns obstructions in the order the guard first reaches them.
+pub fn loop_blocks(grid: &[Vec<char>], pos: (usize, usize), dir_i: usize) -> Vec<LoopBlock> {
+    let table = JumpTable::new(grid);
+    let mut reached = StateGrid::new(table.shape);
+    reached.insert(pos, dir_i);
+    // Turns seen while checking a candidate.
+    let mut seen = StateGrid::new(table.shape);
+
+    let mut blocks = Vec::new();
+    let mut state = (pos, dir_i);
+    for step in GuardSim::new(grid, pos, dir_i) {
+        let block = step.pos;
+        if step.event == Event::Move && !reached.visited(block) {
+            reached.insert(block, step.dir_i);
+            seen.clear();
+            if let Some(repeated) = find_loop(&table, state, block, &mut seen) {
+                blocks.push(LoopBlock {
+                    block,
+                    cycle: cycle(&table, repeated, block),
+                });
+            }
+        }
+        state = (step.pos, step.dir_i);
+    }
+    blocks
+}
+
+/// Walk from `state` with obstacle at `block`.
+///
+/// Returns the first repeated turn, or None if the guard leaves the map.
+fn find_loop(
+    table: &JumpTable,
+    state: State,
+    block: (usize, usize),
+    seen: &mut StateGrid,
+) -> Option<State> {
+    let (mut pos, mut dir_i) = state;
+    while let Some(stop) = table.jump(pos, dir_i, Some(block)) {
+        pos = stop;
+        dir_i = (dir_i + 1) % 4;
+        if !seen.insert(pos, dir_i) {
+            return Some((pos, dir_i));
+        }
+    }
+    None
+}
+
+/// List states of the loop through `start`, with obstacle at `block`.
+fn cycle(table: &JumpTable, start: State, block: (usize, usize)) -> Vec<State> {
+    let mut cycle = Vec::new();
+    let (mut pos, mut dir_i) = start;
+    loop {
+        let stop = table
+            .jump(pos, dir_i, Some(block))
+            .expect("Guard in a loop stays on the map");
+        let (di, dj) = DIRS[dir_i];
+        while pos != stop {
+            cycle.push((pos, dir_i));
+            pos = ((pos.0 as i32 + di) as usize, (pos.1 as i32 + dj) as usize);
+        }
+        cycle.push((pos, dir_i));
+        dir_i = (dir_i + 1) % 4;
+        if (pos, dir_i) == start {
+            return cycle;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_grid;
+
+    #[test]
+    fn jump_to_obstacles() {
+        let g = parse_grid("..#.\n....\n#..#\n....");
+        let table = JumpTable::new(&g);
+        assert_eq!(table.jump((3, 2), 0, None), Some((1, 2)));
+        assert_eq!(table.jump((2, 1), 1, None), Some((2, 2)));
+        assert_eq!(table.jump((2, 1), 3, None), Some((2, 1)));
+        assert_eq!(table.jump((1, 1), 2, None), None);
+    }
+
+    #[test]
+    fn jump_with_block() {
+        let g = parse_grid("..#.\n....\n#..#\n....");
+        let table = JumpTable::new(&g);
+        assert_eq!(table.jump((3, 2), 0, Some((2, 2))), Some((3, 2)));
+        assert_eq!(table.jump((3, 2), 0, Some((0, 2))), Some((1, 2)));
+        assert_eq!(table.jump((3, 2), 0, Some((2, 1))), Some((1, 2)));
+        assert_eq!(table.jump((1, 1), 2, Some((3, 1))), Some((2, 1)));
+        assert_eq!(table.jump((1, 1), 2, Some((0, 1))), None);
+    }
+
+    #[test]
+    fn loop_cycle() {
+        let g = parse_grid(".#..\n...#\n....\n..#.");
+        let blocks = loop_blocks(&g, (2, 1), 0);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block, (2, 0));
+        assert_eq!(
+            blocks[0].cycle,
+            vec![
+                ((2, 1), 0),
+                ((1, 1), 0),
+                ((1, 1), 1),
+                ((1, 2), 1),
+                ((1, 2), 2),
+                ((2, 2), 2),
+                ((2, 2), 3),
+                ((2, 1), 3),
+            ]
+        );
+    }
+}
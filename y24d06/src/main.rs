@@ -1,125 +1,244 @@
-use itertools::Itertools;
+use sim::{Event, GuardSim, Rules, Turn};
 use std::{
     collections::HashSet,
-    io::{self, Read},
+    env,
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
 };
 
+mod jump;
+mod render;
+mod sim;
+mod states;
+
 /// Day 6: Guard Gallivant
 ///
 /// Process ASCII map and move actor based on rules.
 ///
+/// Run with argument `blocks` to list obstruction positions, which make the
+/// guard loop, with the length of the loop. Run with arguments `loop <i>,<j>`
+/// to draw the loop caused by the obstruction at (i, j).
+///
+/// Run with argument `animate` to show the guard walk in the terminal.
+/// Options `--fps <n>` (default 10) set the frame rate, and `--loop <i>,<j>`
+/// adds an obstruction at (i, j) to replay the loop it causes.
+///
+/// Run with argument `variant` to walk every guard on the map with custom
+/// rules, listing its start, number of visited squares and whether it loops.
+/// Options are `--turn <right|left|around>`, `--obstacles <chars>` (default
+/// `#`), `--wrap` for wrap-around edges, `--stride <n>` to leap n squares per
+/// step and `--pause <chars>` for tiles, on which guards pause for a step.
+///
 /// <https://adventofcode.com/2024/day/6>
 fn main() -> io::Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
+    if env::args().nth(1).as_deref() == Some("blocks") {
+        let grid = parse_grid(&input);
+        let (pos, dir_i) = find_guard(&grid).ok_or_else(|| io::Error::other("Guard not found"))?;
+        for loop_block in jump::loop_blocks(&grid, pos, dir_i) {
+            println!(
+                "{},{}\t{}",
+                loop_block.block.0,
+                loop_block.block.1,
+                loop_block.cycle.len()
+            );
+        }
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("loop") {
+        let block = env::args()
+            .nth(2)
+            .as_deref()
+            .and_then(parse_position)
+            .ok_or_else(|| io::Error::other("loop requires position <i>,<j>"))?;
+        let grid = parse_grid(&input);
+        let (pos, dir_i) = find_guard(&grid).ok_or_else(|| io::Error::other("Guard not found"))?;
+        let loop_block = jump::loop_blocks(&grid, pos, dir_i)
+            .into_iter()
+            .find(|b| b.block == block)
+            .ok_or_else(|| io::Error::other("obstruction does not cause a loop"))?;
+        print!("{}", render::render_loop(&grid, &loop_block));
+        return Ok(());
+    }
+    if env::args().nth(1).as_deref() == Some("animate") {
+        return animate(&input, env::args().skip(2));
+    }
+    if env::args().nth(1).as_deref() == Some("variant") {
+        let rules = parse_rules(env::args().skip(2))?;
+        let grid = parse_grid(&input);
+        for (pos, dir_i) in find_guards(&grid) {
+            let mut visited = HashSet::from([pos]);
+            let mut looped = false;
+            for step in GuardSim::with_rules(&grid, pos, dir_i, rules.clone()) {
+                visited.insert(step.pos);
+                looped = step.event == Event::Loop;
+            }
+            println!(
+                "{},{}\t{}\t{}",
+                pos.0,
+                pos.1,
+                visited.len(),
+                if looped { "loop" } else { "exit" }
+            );
+        }
+        return Ok(());
+    }
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
     Ok(())
 }
 
-/// Calculate number of squares, which the guard has visited.
-fn part1(input: &str) -> usize {
-    let grid: Vec<Vec<char>> = input
-        .lines()
-        .map(|line| line.chars().map(|c| c.to_owned()).collect())
-        .collect();
-    let shape = (grid.len(), grid[0].len());
-    let (pos, dir_i) = find_guard(&grid).expect("Guard not found");
-
-    walk_guard(&pos, dir_i, &shape, &grid)
-        .unwrap()
-        .1
-        .iter()
-        .unique()
-        .count()
+/// Parse position `<i>,<j>`.
+fn parse_position(s: &str) -> Option<(usize, usize)> {
+    let (i, j) = s.split_once(',')?;
+    Some((i.parse().ok()?, j.parse().ok()?))
 }
 
-/// Perform guard walk
-///
-/// * `pos`  - Initial position.
-/// * `dir_i` - Initial direction, where 0=N, 1=E, 2=S, 3=W.
-/// * `shape` - Room dimensions (i, j).
-/// * `grid` - Original grid.
-///
-/// Returns visited squares (i, j), or None if path is looped.
-fn walk_guard(
-    pos: &(usize, usize),
-    dir_i: usize,
-    shape: &(usize, usize),
-    grid: &[Vec<char>],
-) -> Option<(Vec<Vec<[bool; 4]>>, Vec<(usize, usize)>)> {
-    let dirs = [
-        (-1, 0), // UP
-        (0, 1),  // RIGHT
-        (1, 0),  // DOWN
-        (0, -1), // LEFT
-    ];
-    let mut dir_i = dir_i.clone();
-    let shape = (shape.0 as i32, shape.1 as i32);
-
-    let mut pos = (pos.0 as i32, pos.1 as i32);
-    let mut next = pos.clone();
-    let mut visited: Vec<Vec<[bool; 4]>> = (0..shape.0)
-        .map(|_| (0..shape.1).map(|_| [false, false, false, false]).collect())
-        .collect();
-    let mut path = Vec::new();
-    loop {
-        pos = (next.0, next.1);
-        path.push((pos.0 as usize, pos.1 as usize));
-
-        if visited[pos.0 as usize][pos.1 as usize][dir_i] {
-            // Been there, loop detected.
-            return None;
+/// Animate guard walk in the terminal, with options from command line.
+fn animate(input: &str, mut args: impl Iterator<Item = String>) -> io::Result<()> {
+    let mut fps = 10.0;
+    let mut block = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fps" => {
+                fps = args
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .filter(|f: &f64| *f > 0.0)
+                    .ok_or_else(|| io::Error::other("--fps requires a positive number"))?;
+            }
+            "--loop" => {
+                block = Some(
+                    args.next()
+                        .as_deref()
+                        .and_then(parse_position)
+                        .ok_or_else(|| io::Error::other("--loop requires position <i>,<j>"))?,
+                );
+            }
+            _ => return Err(io::Error::other(format!("unknown option {}", arg))),
         }
-        visited[pos.0 as usize][pos.1 as usize][dir_i] = true;
-
-        next = (pos.0 as i32 + dirs[dir_i].0, pos.1 as i32 + dirs[dir_i].1);
+    }
+    let mut grid = parse_grid(input);
+    let (pos, dir_i) = find_guard(&grid).ok_or_else(|| io::Error::other("Guard not found"))?;
+    if let Some((i, j)) = block {
+        if grid.get(i).and_then(|row| row.get(j)) != Some(&'.') {
+            return Err(io::Error::other("obstruction must be on an empty square"));
+        }
+        grid[i][j] = '#';
+    }
 
-        if (next.0 < 0) || (next.0 >= shape.0) || (next.1 < 0) || (next.1 >= shape.1) {
-            break;
+    let delay = Duration::from_secs_f64(1.0 / fps);
+    let mut visited = vec![vec![false; grid[0].len()]; grid.len()];
+    visited[pos.0][pos.1] = true;
+    let mut out = io::stdout().lock();
+    // Clear screen, then move cursor home for each frame.
+    write!(out, "\x1b[2J")?;
+    write!(
+        out,
+        "\x1b[H{}",
+        render::render_frame(&grid, &visited, (pos, dir_i), block)
+    )?;
+    out.flush()?;
+    for step in GuardSim::new(&grid, pos, dir_i) {
+        thread::sleep(delay);
+        visited[step.pos.0][step.pos.1] = true;
+        let frame = render::render_frame(&grid, &visited, (step.pos, step.dir_i), block);
+        write!(out, "\x1b[H{}", frame)?;
+        match step.event {
+            Event::Exit => writeln!(out, "Guard left the map")?,
+            Event::Loop => writeln!(out, "Guard is in a loop")?,
+            Event::Move | Event::Turn | Event::Pause => {}
         }
-        if grid[next.0 as usize][next.1 as usize] == '#' {
-            dir_i = (dir_i + 1) % 4;
-            next = pos.clone(); // Do not go forward, just turn
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Parse guard rules from command line options.
+fn parse_rules(mut args: impl Iterator<Item = String>) -> io::Result<Rules> {
+    let mut rules = Rules::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--turn" => {
+                rules.turn = match args.next().as_deref() {
+                    Some("right") => Turn::Right,
+                    Some("left") => Turn::Left,
+                    Some("around") => Turn::Around,
+                    _ => return Err(io::Error::other("--turn requires right, left or around")),
+                };
+            }
+            "--obstacles" => {
+                rules.obstacles = args
+                    .next()
+                    .ok_or_else(|| io::Error::other("--obstacles requires characters"))?
+                    .chars()
+                    .collect();
+            }
+            "--wrap" => rules.wrap = true,
+            "--stride" => {
+                rules.stride = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| io::Error::other("--stride requires a positive number"))?;
+            }
+            "--pause" => {
+                rules.pause_on = args
+                    .next()
+                    .ok_or_else(|| io::Error::other("--pause requires characters"))?
+                    .chars()
+                    .collect();
+            }
+            _ => return Err(io::Error::other(format!("unknown option {}", arg))),
         }
     }
-    Some((visited, path))
+    Ok(rules)
+}
+
+/// Calculate number of squares, which the guard has visited.
+fn part1(input: &str) -> usize {
+    let grid = parse_grid(input);
+    let (pos, dir_i) = find_guard(&grid).expect("Guard not found");
+
+    let mut sim = GuardSim::new(&grid, pos, dir_i);
+    sim.by_ref().for_each(drop);
+    sim.visited_cells()
+}
+
+/// Parse room map into a grid of characters.
+fn parse_grid(input: &str) -> Vec<Vec<char>> {
+    input.lines().map(|line| line.chars().collect()).collect()
 }
 
 /// Get guard position from the grid
 ///
 /// Returns ((i, j), <guard_dir>), where guard_dir 0=N, 1=E, 2=S, 3=W.
 fn find_guard(grid: &[Vec<char>]) -> Option<((usize, usize), usize)> {
+    find_guards(grid).into_iter().next()
+}
+
+/// Get positions and directions of all guards, in reading order.
+fn find_guards(grid: &[Vec<char>]) -> Vec<((usize, usize), usize)> {
     let guard_dirs = ['^', '>', 'v', '<'];
+    let mut guards = Vec::new();
     for (i, row) in grid.iter().enumerate() {
         for (j, c) in row.iter().enumerate() {
             if let Some(p) = guard_dirs.iter().position(|x| x == c) {
-                return Some(((i, j), p));
+                guards.push(((i, j), p));
             }
         }
     }
-    None
+    guards
 }
 
 /// How many possible blocking positions are there
 fn part2(input: &str) -> usize {
-    let mut grid: Vec<Vec<char>> = input
-        .lines()
-        .map(|line| line.chars().map(|c| c.to_owned()).collect())
-        .collect();
-    let shape = (grid.len(), grid[0].len());
+    let grid = parse_grid(input);
     let (pos, dir_i) = find_guard(&grid).expect("Guard not found");
 
-    let mut loop_blocks = HashSet::new();
-    let (visited, guard_path) = walk_guard(&pos, dir_i, &shape, &grid).unwrap();
-    for next in guard_path[1..].iter().dedup() {
-        grid[next.0][next.1] = '#';
-        if walk_guard(&pos, dir_i, &shape, &grid).is_none() {
-            loop_blocks.insert(next.clone());
-        }
-        grid[next.0][next.1] = '.';
-    }
-    loop_blocks.iter().count()
+    jump::loop_blocks(&grid, pos, dir_i).len()
 }
 
 #[cfg(test)]
@@ -149,7 +268,7 @@ mod tests {
 .....#
 ..^.#."
             .trim();
-        assert_eq!(part1(&s), 6);
+        assert_eq!(part1(s), 6);
     }
 
     /// Expect block to be placed at (0, 3)
@@ -161,21 +280,74 @@ mod tests {
 ^.#..#
 ....#."
             .trim();
-        assert_eq!(part2(&s), 1);
+        assert_eq!(part2(s), 1);
     }
 
     #[test]
     fn test_find_guard() {
-        let grid: Vec<Vec<char>> = SAMPLE
-            .lines()
-            .map(|line| line.chars().map(|c| c.clone()).collect())
-            .collect();
+        let grid = parse_grid(SAMPLE);
         let guard_pos = find_guard(&grid).unwrap();
         assert_eq!(guard_pos, ((6, 4), 0));
     }
 
+    #[test]
+    fn test_find_guards() {
+        let grid = parse_grid("..v\n<..\n.#^");
+        assert_eq!(
+            find_guards(&grid),
+            vec![((0, 2), 2), ((1, 0), 3), ((2, 2), 0)]
+        );
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(part2(SAMPLE), 6);
     }
+
+    /// Reference for part 2, walking the whole path for every candidate.
+    fn part2_brute(input: &str) -> usize {
+        let mut grid = parse_grid(input);
+        let (pos, dir_i) = find_guard(&grid).expect("Guard not found");
+
+        let mut loop_blocks = HashSet::new();
+        let guard_path: Vec<(usize, usize)> = GuardSim::new(&grid, pos, dir_i)
+            .filter(|step| step.event == Event::Move && step.pos != pos)
+            .map(|step| step.pos)
+            .collect();
+        for next in guard_path {
+            if loop_blocks.contains(&next) {
+                continue;
+            }
+            grid[next.0][next.1] = '#';
+            let last = GuardSim::new(&grid, pos, dir_i).last();
+            if last.is_some_and(|step| step.event == Event::Loop) {
+                loop_blocks.insert(next);
+            }
+            grid[next.0][next.1] = '.';
+        }
+        loop_blocks.len()
+    }
+
+    /// Jump tables agree with brute force on pseudo-random rooms.
+    #[test]
+    fn part2_matches_brute() {
+        let mut seed: u64 = 6;
+        for _ in 0..50 {
+            let mut room = String::new();
+            for i in 0..12 {
+                for j in 0..15 {
+                    seed = seed
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    room.push(match (i, j) {
+                        (8, 7) => '^',
+                        _ if seed >> 59 < 3 => '#',
+                        _ => '.',
+                    });
+                }
+                room.push('\n');
+            }
+            assert_eq!(part2(&room), part2_brute(&room), "{}", room);
+        }
+    }
 }
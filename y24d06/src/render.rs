@@ -0,0 +1,110 @@
+use crate::jump::{LoopBlock, State};
+
+/// Draw loop on the room map, like the puzzle illustrations.
+///
+/// Squares walked north-south are `|`, east-west `-` and both `+`. The
+/// obstruction is `O`. Other squares are as in the map.
+pub fn render_loop(grid: &[Vec<char>], loop_block: &LoopBlock) -> String {
+    let mut canvas = grid.to_vec();
+    for &((i, j), dir_i) in &loop_block.cycle {
+        let line = if dir_i % 2 == 0 { '|' } else { '-' };
+        canvas[i][j] = match canvas[i][j] {
+            '.' => line,
+            c if (c == '|' || c == '-') && c != line => '+',
+            c => c,
+        };
+    }
+    canvas[loop_block.block.0][loop_block.block.1] = 'O';
+    canvas
+        .iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+/// Guard glyphs for directions 0=N, 1=E, 2=S, 3=W.
+const GUARD: [char; 4] = ['^', '>', 'v', '<'];
+
+/// Draw animation frame of the guard walk.
+///
+/// Visited squares are `X`, the guard is drawn facing its direction and the
+/// added obstruction `block`, if any, is `O`.
+pub fn render_frame(
+    grid: &[Vec<char>],
+    visited: &[Vec<bool>],
+    state: State,
+    block: Option<(usize, usize)>,
+) -> String {
+    let ((gi, gj), dir_i) = state;
+    let mut frame = String::with_capacity(grid.len() * (grid[0].len() + 1));
+    for (i, row) in grid.iter().enumerate() {
+        for (j, &c) in row.iter().enumerate() {
+            frame.push(if (i, j) == (gi, gj) {
+                GUARD[dir_i]
+            } else if Some((i, j)) == block {
+                'O'
+            } else if visited[i][j] {
+                'X'
+            } else if GUARD.contains(&c) {
+                '.'
+            } else {
+                c
+            });
+        }
+        frame.push('\n');
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jump::loop_blocks;
+
+    #[test]
+    fn render_sample_loop() {
+        let map = "....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...";
+        let grid: Vec<Vec<char>> = map.lines().map(|line| line.chars().collect()).collect();
+        let blocks = loop_blocks(&grid, (6, 4), 0);
+        let first = blocks.iter().find(|b| b.block == (6, 3)).unwrap();
+        assert_eq!(
+            render_loop(&grid, first),
+            "....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#...
+"
+        );
+    }
+
+    #[test]
+    fn frame() {
+        let grid: Vec<Vec<char>> = ["..#", "...", ".^."]
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+        let visited = vec![
+            vec![false, false, false],
+            vec![false, true, true],
+            vec![false, true, false],
+        ];
+        assert_eq!(
+            render_frame(&grid, &visited, ((1, 2), 1), Some((2, 2))),
+            "..#\n.X>\n.XO\n"
+        );
+    }
+}
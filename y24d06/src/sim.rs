@@ -0,0 +1,316 @@
+use crate::states::StateGrid;
+
+/// Steps (di, dj) for directions 0=N, 1=E, 2=S, 3=W.
+pub const DIRS: [(i32, i32); 4] = [
+    (-1, 0), // UP
+    (0, 1),  // RIGHT
+    (1, 0),  // DOWN
+    (0, -1), // LEFT
+];
+
+/// What the guard did on a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// Moved forward to a new square.
+    Move,
+    /// Turned in place, facing an obstacle.
+    Turn,
+    /// Stayed in place for a step, after moving onto a marked tile.
+    Pause,
+    /// Is about to walk off the map. Last event of the walk.
+    Exit,
+    /// Returned to a position and direction seen before, so the walk would
+    /// repeat forever. Last event of the walk.
+    Loop,
+}
+
+/// Which way the guard turns, facing an obstacle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Turn {
+    Right,
+    Left,
+    Around,
+}
+
+/// Rules of the guard walk.
+#[derive(Clone, Debug)]
+pub struct Rules {
+    pub turn: Turn,
+    /// Characters, which block the guard.
+    pub obstacles: Vec<char>,
+    /// Guard walking off an edge enters from the opposite edge.
+    pub wrap: bool,
+    /// Squares moved per step. The guard leaps over squares in between, and
+    /// turns if the square it would land on is an obstacle.
+    pub stride: usize,
+    /// Characters of tiles, on which the guard pauses for a step.
+    pub pause_on: Vec<char>,
+}
+
+impl Default for Rules {
+    /// Rules of the puzzle: step forward or turn right on `#`.
+    fn default() -> Self {
+        Rules {
+            turn: Turn::Right,
+            obstacles: vec!['#'],
+            wrap: false,
+            stride: 1,
+            pause_on: Vec::new(),
+        }
+    }
+}
+
+/// Guard state after a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    pub pos: (usize, usize),
+    /// Direction, where 0=N, 1=E, 2=S, 3=W.
+    pub dir_i: usize,
+    pub event: Event,
+}
+
+/// Step-wise simulation of the guard walk.
+///
+/// The guard moves forward until there is an obstacle in front, when it
+/// turns, according to [`Rules`]. Iterating yields the state after each
+/// step, ending in [`Event::Exit`] or [`Event::Loop`].
+pub struct GuardSim<'a> {
+    grid: &'a [Vec<char>],
+    rules: Rules,
+    shape: (usize, usize),
+    pos: (usize, usize),
+    dir_i: usize,
+    /// Guard pauses on the next step.
+    pausing: bool,
+    /// Seen positions and directions.
+    visited: StateGrid,
+    done: bool,
+}
+
+impl<'a> GuardSim<'a> {
+    /// Start simulation with the rules of the puzzle.
+    ///
+    /// * `grid` - Room map, with obstacles `#`.
+    /// * `pos`  - Initial position.
+    /// * `dir_i` - Initial direction, where 0=N, 1=E, 2=S, 3=W.
+    pub fn new(grid: &'a [Vec<char>], pos: (usize, usize), dir_i: usize) -> Self {
+        GuardSim::with_rules(grid, pos, dir_i, Rules::default())
+    }
+
+    /// Start simulation with custom rules.
+    pub fn with_rules(
+        grid: &'a [Vec<char>],
+        pos: (usize, usize),
+        dir_i: usize,
+        rules: Rules,
+    ) -> Self {
+        assert!(rules.stride > 0, "stride must be positive");
+        let shape = (grid.len(), grid[0].len());
+        let mut visited = StateGrid::new(shape);
+        visited.insert(pos, dir_i);
+        GuardSim {
+            grid,
+            rules,
+            shape,
+            pos,
+            dir_i,
+            pausing: false,
+            visited,
+            done: false,
+        }
+    }
+}
+
+impl GuardSim<'_> {
+    /// Number of squares the guard has been on so far.
+    pub fn visited_cells(&self) -> usize {
+        self.visited.cells()
+    }
+}
+
+impl Iterator for GuardSim<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.pausing {
+            self.pausing = false;
+            return Some(Step {
+                pos: self.pos,
+                dir_i: self.dir_i,
+                event: Event::Pause,
+            });
+        }
+        let (di, dj) = DIRS[self.dir_i];
+        let stride = self.rules.stride as i32;
+        let mut next = (
+            self.pos.0 as i32 + di * stride,
+            self.pos.1 as i32 + dj * stride,
+        );
+        if self.rules.wrap {
+            next = (
+                next.0.rem_euclid(self.shape.0 as i32),
+                next.1.rem_euclid(self.shape.1 as i32),
+            );
+        }
+        let mut event = if (next.0 < 0)
+            || (next.0 >= self.shape.0 as i32)
+            || (next.1 < 0)
+            || (next.1 >= self.shape.1 as i32)
+        {
+            Event::Exit
+        } else if self
+            .rules
+            .obstacles
+            .contains(&self.grid[next.0 as usize][next.1 as usize])
+        {
+            self.dir_i = (self.dir_i
+                + match self.rules.turn {
+                    Turn::Right => 1,
+                    Turn::Around => 2,
+                    Turn::Left => 3,
+                })
+                % 4;
+            Event::Turn
+        } else {
+            self.pos = (next.0 as usize, next.1 as usize);
+            self.pausing = self
+                .rules
+                .pause_on
+                .contains(&self.grid[self.pos.0][self.pos.1]);
+            Event::Move
+        };
+        if event != Event::Exit && !self.visited.insert(self.pos, self.dir_i) {
+            // Been there, loop detected.
+            event = Event::Loop;
+        }
+        self.done = matches!(event, Event::Exit | Event::Loop);
+        Some(Step {
+            pos: self.pos,
+            dir_i: self.dir_i,
+            event,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_grid;
+
+    #[test]
+    fn steps_to_exit() {
+        let g = parse_grid(".#.\n...\n.^.");
+        let steps: Vec<Step> = GuardSim::new(&g, (2, 1), 0).collect();
+        let events: Vec<(usize, usize, Event)> =
+            steps.iter().map(|s| (s.pos.0, s.pos.1, s.event)).collect();
+        assert_eq!(
+            events,
+            vec![
+                (1, 1, Event::Move),
+                (1, 1, Event::Turn),
+                (1, 2, Event::Move),
+                (1, 2, Event::Exit),
+            ]
+        );
+        assert_eq!(steps[1].dir_i, 1);
+    }
+
+    #[test]
+    fn loop_detected() {
+        let g = parse_grid(".#..\n...#\n#...\n..#.");
+        let last = GuardSim::new(&g, (2, 1), 0).last().unwrap();
+        assert_eq!(last.event, Event::Loop);
+    }
+
+    #[test]
+    fn break_early() {
+        let g = parse_grid("....\n....\n.^..");
+        let mut sim = GuardSim::new(&g, (2, 1), 0);
+        assert_eq!(sim.next().map(|s| s.pos), Some((1, 1)));
+    }
+
+    #[test]
+    fn turn_left_and_around() {
+        let g = parse_grid(".#.\n...\n.^.");
+        let events = |turn| {
+            let rules = Rules {
+                turn,
+                ..Rules::default()
+            };
+            GuardSim::with_rules(&g, (2, 1), 0, rules)
+                .map(|s| (s.pos, s.event))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            events(Turn::Left),
+            vec![
+                ((1, 1), Event::Move),
+                ((1, 1), Event::Turn),
+                ((1, 0), Event::Move),
+                ((1, 0), Event::Exit),
+            ]
+        );
+        assert_eq!(
+            events(Turn::Around),
+            vec![
+                ((1, 1), Event::Move),
+                ((1, 1), Event::Turn),
+                ((2, 1), Event::Move),
+                ((2, 1), Event::Exit),
+            ]
+        );
+    }
+
+    #[test]
+    fn obstacles_and_stride() {
+        let g = parse_grid("*.\n..\n..\n*.\n^.");
+        let rules = Rules {
+            obstacles: vec!['*'],
+            stride: 2,
+            ..Rules::default()
+        };
+        let steps: Vec<((usize, usize), Event)> = GuardSim::with_rules(&g, (4, 0), 0, rules)
+            .map(|s| (s.pos, s.event))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                ((2, 0), Event::Move),
+                ((2, 0), Event::Turn),
+                ((2, 0), Event::Exit),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrap_around() {
+        let g = parse_grid("...\n.^.\n...");
+        let rules = Rules {
+            wrap: true,
+            ..Rules::default()
+        };
+        let steps: Vec<Step> = GuardSim::with_rules(&g, (1, 1), 0, rules).collect();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[1].pos, (2, 1));
+        assert_eq!(steps[2].event, Event::Loop);
+    }
+
+    #[test]
+    fn pause_on_marked() {
+        let g = parse_grid(".\n+\n^");
+        let rules = Rules {
+            pause_on: vec!['+'],
+            ..Rules::default()
+        };
+        let events: Vec<Event> = GuardSim::with_rules(&g, (2, 0), 0, rules)
+            .map(|s| s.event)
+            .collect();
+        assert_eq!(
+            events,
+            vec![Event::Move, Event::Pause, Event::Move, Event::Exit]
+        );
+    }
+}
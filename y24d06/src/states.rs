@@ -0,0 +1,110 @@
+/// Cells packed in a word, at 4 bits per cell.
+const CELLS_PER_WORD: usize = 16;
+
+/// Set of guard states, with a bit per square and direction.
+///
+/// Words carry the epoch in which they were last written, and words from
+/// earlier epochs read as empty, so clearing is O(1).
+pub struct StateGrid {
+    width: usize,
+    bits: Vec<u64>,
+    epochs: Vec<u32>,
+    epoch: u32,
+    /// Number of squares with any direction set.
+    cells: usize,
+}
+
+impl StateGrid {
+    /// Empty set for a room of `shape` (i, j).
+    pub fn new(shape: (usize, usize)) -> Self {
+        let words = (shape.0 * shape.1).div_ceil(CELLS_PER_WORD);
+        StateGrid {
+            width: shape.1,
+            bits: vec![0; words],
+            epochs: vec![0; words],
+            epoch: 0,
+            cells: 0,
+        }
+    }
+
+    /// Remove all states.
+    pub fn clear(&mut self) {
+        self.cells = 0;
+        self.epoch = self.epoch.wrapping_add(1);
+        if self.epoch == 0 {
+            // Epochs wrapped around, old words could look current.
+            self.bits.fill(0);
+            self.epochs.fill(0);
+        }
+    }
+
+    /// Add state at `pos` with direction `dir_i`.
+    ///
+    /// Returns true if the state was not present.
+    pub fn insert(&mut self, pos: (usize, usize), dir_i: usize) -> bool {
+        let (word, shift) = self.locate(pos);
+        if self.epochs[word] != self.epoch {
+            self.epochs[word] = self.epoch;
+            self.bits[word] = 0;
+        }
+        let cell = (self.bits[word] >> shift) & 0xf;
+        let bit = 1 << dir_i;
+        if cell & bit != 0 {
+            return false;
+        }
+        if cell == 0 {
+            self.cells += 1;
+        }
+        self.bits[word] |= bit << shift;
+        true
+    }
+
+    /// Is there a state at `pos` in any direction.
+    pub fn visited(&self, pos: (usize, usize)) -> bool {
+        let (word, shift) = self.locate(pos);
+        self.epochs[word] == self.epoch && (self.bits[word] >> shift) & 0xf != 0
+    }
+
+    /// Number of squares with a state in any direction.
+    pub fn cells(&self) -> usize {
+        self.cells
+    }
+
+    /// Word index and bit offset of the square at `pos`.
+    fn locate(&self, pos: (usize, usize)) -> (usize, usize) {
+        let index = pos.0 * self.width + pos.1;
+        (index / CELLS_PER_WORD, (index % CELLS_PER_WORD) * 4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_clear() {
+        let mut states = StateGrid::new((5, 7));
+        assert!(states.insert((4, 6), 3));
+        assert!(states.insert((4, 6), 0));
+        assert!(!states.insert((4, 6), 3));
+        assert!(states.insert((0, 0), 1));
+        assert!(states.visited((4, 6)));
+        assert!(!states.visited((4, 5)));
+        assert_eq!(states.cells(), 2);
+
+        states.clear();
+        assert!(!states.visited((4, 6)));
+        assert_eq!(states.cells(), 0);
+        assert!(states.insert((4, 6), 3));
+    }
+
+    #[test]
+    fn epoch_wraps() {
+        let mut states = StateGrid::new((2, 2));
+        states.insert((1, 1), 2);
+        states.epoch = u32::MAX;
+        states.clear();
+        assert!(!states.visited((1, 1)));
+        assert!(states.insert((1, 1), 2));
+    }
+}
@@ -1,112 +1,123 @@
-use itertools::Itertools;
-use std::io::{self, Read};
+use ops::{Add, Concat, Mul, Operator};
+use solutions::{count_solutions, equation, Solutions};
+use std::{
+    env,
+    io::{self, Read},
+};
+
+mod ops;
+mod solutions;
 
 /// Day 7: Bridge Repair
 ///
+/// Run with arguments `ops <names>` to sum results of equations, which can
+/// match with the comma separated operators, such as `add,mul,sub,cat2`. See
+/// [`ops::by_name`] for the names.
+///
+/// Run with arguments `solutions [names]` to print every matching equation,
+/// or `count [names]` to print the number of matches of each equation.
+/// Operators default to `add,mul,cat`.
+///
 /// <https://adventofcode.com/2024/day/7>
 fn main() -> io::Result<()> {
     let mut input = String::new();
     io::stdin().read_to_string(&mut input)?;
+    if env::args().nth(1).as_deref() == Some("ops") {
+        let names = env::args()
+            .nth(2)
+            .ok_or_else(|| io::Error::other("ops requires operator names"))?;
+        let operators = parse_operators(&names)?;
+        let operators: Vec<&dyn Operator> = operators.iter().map(|op| op.as_ref()).collect();
+        let symbols: Vec<String> = operators.iter().map(|op| op.symbol()).collect();
+        println!(
+            "Result with {}: {}",
+            symbols.join(" "),
+            calibration(&input, &operators)
+        );
+        return Ok(());
+    }
+    if let Some(mode @ ("solutions" | "count")) = env::args().nth(1).as_deref() {
+        let names = env::args().nth(2).unwrap_or("add,mul,cat".to_string());
+        let operators = parse_operators(&names)?;
+        let operators: Vec<&dyn Operator> = operators.iter().map(|op| op.as_ref()).collect();
+        for (result, parts) in input.lines().filter_map(parse_line) {
+            if mode == "count" {
+                println!(
+                    "{}\t{}",
+                    count_solutions(result, &operators, &parts),
+                    result
+                );
+                continue;
+            }
+            for solution in Solutions::new(result, &operators, &parts) {
+                println!("{}", equation(result, &parts, &solution));
+            }
+        }
+        return Ok(());
+    }
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
     Ok(())
 }
 
-#[derive(Clone, Debug, PartialEq)]
-enum Op {
-    ADD, // +
-    MUL, // *
-    CON, // ||
+/// Sum results of lines, whose arithmetics can match with any of `ops`.
+fn calibration(input: &str, ops: &[&dyn Operator]) -> u64 {
+    input
+        .lines()
+        .filter_map(parse_line)
+        .filter_map(|(result, parts)| find_ops(result, ops, &parts).map(|_ops| result))
+        .sum()
+}
+
+/// Parse equation `<result>: <part>...`.
+///
+/// Returns None if the line is not an equation with at least one part.
+fn parse_line(line: &str) -> Option<(u64, Vec<u32>)> {
+    let (result, parts) = line.split_once(':')?;
+    let result = result.parse::<u64>().ok()?;
+    let parts: Vec<u32> = parts
+        .split_whitespace()
+        .filter_map(|p| p.parse().ok())
+        .collect();
+    (!parts.is_empty()).then_some((result, parts))
+}
+
+/// Parse comma separated operator names.
+fn parse_operators(names: &str) -> io::Result<Vec<Box<dyn Operator>>> {
+    names
+        .split(',')
+        .map(|name| {
+            ops::by_name(name).ok_or_else(|| io::Error::other(format!("unknown operator {}", name)))
+        })
+        .collect()
 }
 
 /// Find lines, whose arithmetics can match.
 fn part1(input: &str) -> u64 {
-    input
-        .lines()
-        .filter_map(|line| {
-            if let Some((result, parts)) = line.split_once(':') {
-                let result = result.parse::<u64>();
-                let parts: Vec<u32> = parts
-                    .split_whitespace()
-                    .filter_map(|p| p.parse().ok())
-                    .collect();
-                match (result, parts) {
-                    (Ok(r), parts) => {
-                        if let Some(_ops) = find_ops(r, &[Op::ADD, Op::MUL], &parts) {
-                            Some(r as u64)
-                        } else {
-                            None
-                        }
-                    }
-                    _ => None,
-                }
-            } else {
-                None
-            }
-        })
-        .sum()
+    calibration(input, &[&Add, &Mul])
 }
 
-fn find_ops(result: u64, ops: &[Op], parts: &[u32]) -> Option<Vec<Op>> {
-    let pl: usize = (parts.len() - 1) as usize;
-    let perms = itertools::repeat_n(ops, pl).multi_cartesian_product();
-    'p: for op_candidate in perms {
-        let mut running = parts[0] as u64;
-        for (i, op) in op_candidate.iter().enumerate() {
-            let op_res = match op {
-                Op::ADD => running.checked_add(parts[i + 1] as u64),
-                Op::MUL => running.checked_mul(parts[i + 1] as u64),
-                Op::CON => running
-                    .checked_mul(10_u32.pow(parts[i + 1].ilog10() + 1) as u64)
-                    .and_then(|v| v.checked_add(parts[i + 1] as u64)),
-            };
-            if op_res.is_none_or(|r| r > result) {
-                // Too high, do not continue this branch
-                continue 'p;
-            }
-            running = op_res.unwrap();
-        }
-        if running == result {
-            return Some(op_candidate.into_iter().map(|v| v.clone()).collect());
-        }
-    }
-    None
+/// Find operators, which evaluated left to right give `result`.
+///
+/// Returns the first match in the order of `ops`, varying the last operator
+/// fastest.
+fn find_ops<'a>(
+    result: u64,
+    ops: &[&'a dyn Operator],
+    parts: &[u32],
+) -> Option<Vec<&'a dyn Operator>> {
+    Solutions::new(result, ops, parts).next()
 }
 
-/// First match with ADD and MUL, then include CON if did not succeed
+/// Find lines, whose arithmetics can match, also with concatenation.
 fn part2(input: &str) -> u64 {
-    input
-        .lines()
-        .filter_map(|line| {
-            if let Some((result, parts)) = line.split_once(':') {
-                let result = result.parse::<u64>();
-                let parts: Vec<u32> = parts
-                    .split_whitespace()
-                    .filter_map(|p| p.parse().ok())
-                    .collect();
-                match (result, parts) {
-                    (Ok(r), parts) => {
-                        if let Some(_ops) = find_ops(r, &[Op::ADD, Op::MUL], &parts) {
-                            Some(r as u64)
-                        } else if let Some(_ops) = find_ops(r, &[Op::ADD, Op::MUL, Op::CON], &parts)
-                        {
-                            Some(r as u64)
-                        } else {
-                            None
-                        }
-                    }
-                    _ => None,
-                }
-            } else {
-                None
-            }
-        })
-        .sum()
+    calibration(input, &[&Add, &Mul, &Concat::DECIMAL])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ops::{Div, Pow, Sub, Xor};
 
     static SAMPLE: &str = "190: 10 19
 3267: 81 40 27
@@ -118,6 +129,10 @@ mod tests {
 21037: 9 7 18 13
 292: 11 6 16 20";
 
+    fn symbols(ops: Option<Vec<&dyn Operator>>) -> Option<Vec<String>> {
+        ops.map(|ops| ops.iter().map(|op| op.symbol()).collect())
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1(SAMPLE), 3749);
@@ -125,17 +140,90 @@ mod tests {
 
     #[test]
     fn test_find_ops1() {
-        let ops = find_ops(292, &[Op::ADD, Op::MUL], &[11, 6, 16, 20]);
-        assert_eq!(ops, Some(vec![Op::ADD, Op::MUL, Op::ADD]));
+        let ops = find_ops(292, &[&Add, &Mul], &[11, 6, 16, 20]);
+        assert_eq!(symbols(ops), Some(vec!["+".into(), "*".into(), "+".into()]));
     }
     #[test]
     fn test_find_ops2() {
-        let ops = find_ops(190, &[Op::ADD, Op::MUL], &[10, 19]);
-        assert_eq!(ops, Some(vec![Op::MUL]));
+        let ops = find_ops(190, &[&Add, &Mul], &[10, 19]);
+        assert_eq!(symbols(ops), Some(vec!["*".into()]));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("190: 10 19"), Some((190, vec![10, 19])));
+        assert_eq!(parse_line("5:"), None);
+        assert_eq!(parse_line("5: x"), None);
+        assert_eq!(part1("5:\n190: 10 19"), 190);
     }
 
     #[test]
     fn test_part2() {
         assert_eq!(part2(SAMPLE), 11387);
     }
+
+    #[test]
+    fn custom_operators() {
+        let ops = find_ops(10, &[&Add, &Mul, &Sub], &[10, 5, 40]);
+        assert_eq!(symbols(ops), Some(vec!["*".into(), "-".into()]));
+        let ops = find_ops(4, &[&Div, &Pow], &[7, 3, 2]);
+        assert_eq!(symbols(ops), Some(vec!["/".into(), "**".into()]));
+        let binary = Concat::new(2);
+        let ops = find_ops(5, &[&Xor, &binary], &[1, 0, 1]);
+        assert_eq!(symbols(ops), Some(vec!["||2".into(), "||2".into()]));
+        assert!(find_ops(8, &[&Sub, &Div], &[7, 3, 2]).is_none());
+    }
+
+    /// Reference for `find_ops`, trying every operator sequence in order.
+    fn find_ops_brute<'a>(
+        result: u64,
+        ops: &[&'a dyn Operator],
+        parts: &[u32],
+    ) -> Option<Vec<&'a dyn Operator>> {
+        let mut choice = vec![0; parts.len() - 1];
+        loop {
+            let mut running = Some(parts[0] as u64);
+            for (&c, &part) in choice.iter().zip(&parts[1..]) {
+                running = running.and_then(|r| ops[c].apply(r, part));
+            }
+            if running == Some(result) {
+                return Some(choice.iter().map(|&c| ops[c]).collect());
+            }
+            // Next sequence, varying the last operator fastest.
+            let i = choice.iter().rposition(|&c| c + 1 < ops.len())?;
+            choice[i] += 1;
+            choice[i + 1..].fill(0);
+        }
+    }
+
+    /// Right to left search agrees with brute force on all short equations
+    /// of small parts.
+    #[test]
+    fn matches_brute_force() {
+        let sets: [&[&dyn Operator]; 4] = [
+            &[&Add, &Mul],
+            &[&Add, &Mul, &Concat::DECIMAL],
+            &[&Sub, &Mul, &Div],
+            &[&Xor, &Pow, &Concat::new(3)],
+        ];
+        let values = [0, 1, 2, 7, 12];
+        for len in 2..=4 {
+            for k in 0..values.len().pow(len) {
+                let parts: Vec<u32> = (0..len)
+                    .map(|i| values[k / values.len().pow(i) % values.len()])
+                    .collect();
+                for ops in sets {
+                    for result in 0..100 {
+                        assert_eq!(
+                            symbols(find_ops(result, ops, &parts)),
+                            symbols(find_ops_brute(result, ops, &parts)),
+                            "{}: {:?}",
+                            result,
+                            parts
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
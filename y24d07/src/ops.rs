@@ -0,0 +1,265 @@
+/// Running value before an operator, found from its result.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Inverse {
+    /// The only running value giving the result.
+    Value(u64),
+    /// No running value gives the result.
+    Impossible,
+    /// Running value cannot be found backwards, so values have to be tried
+    /// forwards.
+    Unknown,
+}
+
+/// Binary operator of calibration equations, evaluated left to right.
+pub trait Operator {
+    /// Symbol in printed equations, such as `+`.
+    fn symbol(&self) -> String;
+
+    /// Combine running value with the next part.
+    ///
+    /// Returns None on overflow, or if the result is undefined.
+    fn apply(&self, running: u64, part: u32) -> Option<u64>;
+
+    /// Running value, which combined with `part` gives `result`.
+    fn inverse(&self, _result: u64, _part: u32) -> Inverse {
+        Inverse::Unknown
+    }
+}
+
+/// Addition `+`.
+pub struct Add;
+
+impl Operator for Add {
+    fn symbol(&self) -> String {
+        "+".to_string()
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        running.checked_add(part as u64)
+    }
+
+    fn inverse(&self, result: u64, part: u32) -> Inverse {
+        result
+            .checked_sub(part as u64)
+            .map_or(Inverse::Impossible, Inverse::Value)
+    }
+}
+
+/// Multiplication `*`.
+pub struct Mul;
+
+impl Operator for Mul {
+    fn symbol(&self) -> String {
+        "*".to_string()
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        running.checked_mul(part as u64)
+    }
+
+    fn inverse(&self, result: u64, part: u32) -> Inverse {
+        match part {
+            // Anything times zero is zero.
+            0 if result == 0 => Inverse::Unknown,
+            0 => Inverse::Impossible,
+            _ if result.is_multiple_of(part as u64) => Inverse::Value(result / part as u64),
+            _ => Inverse::Impossible,
+        }
+    }
+}
+
+/// Concatenation `||` of digits in a base.
+pub struct Concat {
+    base: u32,
+}
+
+impl Concat {
+    /// Concatenation of decimal digits, as in the puzzle.
+    pub const DECIMAL: Concat = Concat { base: 10 };
+
+    /// Concatenation of digits in `base`, which is at least 2.
+    pub fn new(base: u32) -> Self {
+        assert!(base >= 2, "base must be at least 2");
+        Concat { base }
+    }
+
+    /// Power of base to shift a value by, when concatenating `part`.
+    fn shift(&self, part: u32) -> Option<u64> {
+        let digits = part.checked_ilog(self.base).unwrap_or(0) + 1;
+        (self.base as u64).checked_pow(digits)
+    }
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> String {
+        match self.base {
+            10 => "||".to_string(),
+            base => format!("||{}", base),
+        }
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        running
+            .checked_mul(self.shift(part)?)?
+            .checked_add(part as u64)
+    }
+
+    fn inverse(&self, result: u64, part: u32) -> Inverse {
+        match self.shift(part) {
+            Some(shift) if result % shift == part as u64 => Inverse::Value(result / shift),
+            _ => Inverse::Impossible,
+        }
+    }
+}
+
+/// Subtraction `-`. Running values may not become negative.
+pub struct Sub;
+
+impl Operator for Sub {
+    fn symbol(&self) -> String {
+        "-".to_string()
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        running.checked_sub(part as u64)
+    }
+
+    fn inverse(&self, result: u64, part: u32) -> Inverse {
+        result
+            .checked_add(part as u64)
+            .map_or(Inverse::Impossible, Inverse::Value)
+    }
+}
+
+/// Integer division `/`, rounding down. Division by zero is undefined.
+///
+/// Not inverted, as many running values divide to the same result.
+pub struct Div;
+
+impl Operator for Div {
+    fn symbol(&self) -> String {
+        "/".to_string()
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        running.checked_div(part as u64)
+    }
+}
+
+/// Exponentiation `**`.
+pub struct Pow;
+
+impl Operator for Pow {
+    fn symbol(&self) -> String {
+        "**".to_string()
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        running.checked_pow(part)
+    }
+
+    fn inverse(&self, result: u64, part: u32) -> Inverse {
+        match part {
+            // Anything to the power of zero is one.
+            0 if result == 1 => Inverse::Unknown,
+            0 => Inverse::Impossible,
+            1 => Inverse::Value(result),
+            _ => {
+                // Floating point root is close, check its neighbours.
+                let root = (result as f64).powf(1.0 / part as f64).round() as u64;
+                (root.saturating_sub(1)..=root + 1)
+                    .find(|r| r.checked_pow(part) == Some(result))
+                    .map_or(Inverse::Impossible, Inverse::Value)
+            }
+        }
+    }
+}
+
+/// Bitwise exclusive or `^`.
+pub struct Xor;
+
+impl Operator for Xor {
+    fn symbol(&self) -> String {
+        "^".to_string()
+    }
+
+    fn apply(&self, running: u64, part: u32) -> Option<u64> {
+        Some(running ^ part as u64)
+    }
+
+    fn inverse(&self, result: u64, part: u32) -> Inverse {
+        Inverse::Value(result ^ part as u64)
+    }
+}
+
+/// Operator by name: `add`, `mul`, `cat`, `sub`, `div`, `pow`, `xor`, or
+/// `cat<N>` for concatenation in base N.
+pub fn by_name(name: &str) -> Option<Box<dyn Operator>> {
+    let operator: Box<dyn Operator> = match name {
+        "add" => Box::new(Add),
+        "mul" => Box::new(Mul),
+        "cat" => Box::new(Concat::DECIMAL),
+        "sub" => Box::new(Sub),
+        "div" => Box::new(Div),
+        "pow" => Box::new(Pow),
+        "xor" => Box::new(Xor),
+        _ => {
+            let base = name.strip_prefix("cat")?.parse().ok()?;
+            if base < 2 {
+                return None;
+            }
+            Box::new(Concat::new(base))
+        }
+    };
+    Some(operator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inverse agrees with forward application.
+    #[test]
+    fn inverse_round_trip() {
+        let operators: Vec<Box<dyn Operator>> = vec![
+            Box::new(Add),
+            Box::new(Mul),
+            Box::new(Concat::DECIMAL),
+            Box::new(Concat::new(2)),
+            Box::new(Sub),
+            Box::new(Div),
+            Box::new(Pow),
+            Box::new(Xor),
+        ];
+        for op in &operators {
+            for running in 0..40 {
+                for part in 0..6 {
+                    let Some(result) = op.apply(running, part) else {
+                        continue;
+                    };
+                    match op.inverse(result, part) {
+                        Inverse::Value(v) => assert_eq!(v, running, "{}", op.symbol()),
+                        Inverse::Impossible => panic!("{} {} {}", running, op.symbol(), part),
+                        Inverse::Unknown => {}
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn concat_bases() {
+        assert_eq!(Concat::DECIMAL.apply(12, 345), Some(12345));
+        assert_eq!(Concat::new(2).apply(0b101, 0b11), Some(0b10111));
+        assert_eq!(Concat::DECIMAL.inverse(12345, 45), Inverse::Value(123));
+        assert_eq!(Concat::DECIMAL.inverse(12345, 44), Inverse::Impossible);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(by_name("cat").unwrap().symbol(), "||");
+        assert_eq!(by_name("cat16").unwrap().symbol(), "||16");
+        assert!(by_name("cat1").is_none());
+        assert!(by_name("mod").is_none());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::ops::{Inverse, Operator};
+use std::collections::BTreeMap;
+
+/// Lazy enumeration of operators, which evaluated left to right give the
+/// result.
+///
+/// Solutions come in the order of `ops`, varying the last operator fastest.
+/// Branches without solutions are pruned, working right to left from the
+/// result.
+pub struct Solutions<'a, 'p> {
+    result: u64,
+    ops: &'p [&'a dyn Operator],
+    parts: &'p [u32],
+    /// Indices of chosen operators.
+    chosen: Vec<usize>,
+    /// Running values, starting with the first part and followed by the
+    /// value after each chosen operator.
+    running: Vec<u64>,
+    started: bool,
+    done: bool,
+}
+
+impl<'a, 'p> Solutions<'a, 'p> {
+    /// Enumerate solutions for non-empty `parts`.
+    pub fn new(result: u64, ops: &'p [&'a dyn Operator], parts: &'p [u32]) -> Self {
+        Solutions {
+            result,
+            ops,
+            parts,
+            chosen: Vec::with_capacity(parts.len() - 1),
+            running: vec![parts[0] as u64],
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Back up to the last chosen operator with alternatives left.
+    ///
+    /// Returns the index of the next operator to try, or None when all
+    /// choices are exhausted.
+    fn backtrack(&mut self) -> Option<usize> {
+        let op_i = self.chosen.pop()?;
+        self.running.pop();
+        Some(op_i + 1)
+    }
+}
+
+impl<'a> Iterator for Solutions<'a, '_> {
+    type Item = Vec<&'a dyn Operator>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut op_i = if self.started {
+            self.backtrack()
+        } else {
+            self.started = true;
+            reaches(self.running[0], self.result, self.ops, &self.parts[1..]).then_some(0)
+        };
+        loop {
+            let Some(from) = op_i else {
+                self.done = true;
+                return None;
+            };
+            let depth = self.chosen.len();
+            if depth == self.parts.len() - 1 {
+                return Some(self.chosen.iter().map(|&i| self.ops[i]).collect());
+            }
+            let running = self.running[depth];
+            // Take the next operator, after which result is still reachable.
+            let found = (from..self.ops.len()).find_map(|i| {
+                let value = self.ops[i].apply(running, self.parts[depth + 1])?;
+                reaches(value, self.result, self.ops, &self.parts[depth + 2..])
+                    .then_some((i, value))
+            });
+            op_i = match found {
+                Some((i, value)) => {
+                    self.chosen.push(i);
+                    self.running.push(value);
+                    Some(0)
+                }
+                None => self.backtrack(),
+            };
+        }
+    }
+}
+
+/// Count operator assignments, which evaluated left to right give `result`.
+pub fn count_solutions(result: u64, ops: &[&dyn Operator], parts: &[u32]) -> u64 {
+    count(parts[0] as u64, result, ops, &parts[1..])
+}
+
+/// Number of ways `start` can be combined with `rest` into `target`.
+fn count(start: u64, target: u64, ops: &[&dyn Operator], rest: &[u32]) -> u64 {
+    let Some((&last, init)) = rest.split_last() else {
+        return (start == target) as u64;
+    };
+    ops.iter()
+        .map(|op| match op.inverse(target, last) {
+            Inverse::Value(prev) => count(start, prev, ops, init),
+            Inverse::Impossible => 0,
+            Inverse::Unknown => value_counts(start, ops, init)
+                .into_iter()
+                .filter(|&(v, _)| op.apply(v, last) == Some(target))
+                .map(|(_, n)| n)
+                .sum(),
+        })
+        .sum()
+}
+
+/// Can `start` be combined with `rest` into `target`.
+///
+/// Works right to left, undoing operators on the last part and pruning
+/// branches, which cannot be undone. Operators without an inverse try every
+/// value reachable forwards.
+fn reaches(start: u64, target: u64, ops: &[&dyn Operator], rest: &[u32]) -> bool {
+    let Some((&last, init)) = rest.split_last() else {
+        return start == target;
+    };
+    ops.iter().any(|op| match op.inverse(target, last) {
+        Inverse::Value(prev) => reaches(start, prev, ops, init),
+        Inverse::Impossible => false,
+        Inverse::Unknown => value_counts(start, ops, init)
+            .into_keys()
+            .any(|v| op.apply(v, last) == Some(target)),
+    })
+}
+
+/// All values from combining `start` with `rest`, with the number of ways to
+/// get each.
+///
+/// Values are merged after each part, as non-invertible operators such as
+/// division tend to give the same values.
+fn value_counts(start: u64, ops: &[&dyn Operator], rest: &[u32]) -> BTreeMap<u64, u64> {
+    let mut values = BTreeMap::from([(start, 1)]);
+    for &part in rest {
+        let mut next = BTreeMap::new();
+        for (&v, &n) in &values {
+            for op in ops {
+                if let Some(value) = op.apply(v, part) {
+                    *next.entry(value).or_insert(0) += n;
+                }
+            }
+        }
+        values = next;
+    }
+    values
+}
+
+/// Equation of a solution, such as `292 = 11 + 6 * 16 + 20`.
+pub fn equation(result: u64, parts: &[u32], ops: &[&dyn Operator]) -> String {
+    let mut s = format!("{} = {}", result, parts[0]);
+    for (op, part) in ops.iter().zip(&parts[1..]) {
+        s += &format!(" {} {}", op.symbol(), part);
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::{Add, Concat, Div, Mul, Sub};
+
+    fn symbols(ops: Vec<&dyn Operator>) -> String {
+        ops.iter()
+            .map(|op| op.symbol())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn enumerate_in_order() {
+        let ops: [&dyn Operator; 2] = [&Add, &Mul];
+        let all: Vec<String> = Solutions::new(3267, &ops, &[81, 40, 27])
+            .map(symbols)
+            .collect();
+        assert_eq!(all, vec!["+ *", "* +"]);
+        assert_eq!(count_solutions(3267, &ops, &[81, 40, 27]), 2);
+        assert_eq!(Solutions::new(83, &ops, &[17, 5]).count(), 0);
+    }
+
+    #[test]
+    fn single_part() {
+        let ops: [&dyn Operator; 1] = [&Add];
+        assert_eq!(Solutions::new(7, &ops, &[7]).count(), 1);
+        assert_eq!(Solutions::new(8, &ops, &[7]).count(), 0);
+        assert_eq!(count_solutions(7, &ops, &[7]), 1);
+    }
+
+    #[test]
+    fn count_matches_enumeration() {
+        let ops: [&dyn Operator; 5] = [&Add, &Mul, &Concat::DECIMAL, &Sub, &Div];
+        let parts = [2, 1, 2, 0, 1, 1];
+        for result in 0..30 {
+            assert_eq!(
+                count_solutions(result, &ops, &parts),
+                Solutions::new(result, &ops, &parts).count() as u64,
+                "{}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn print_equation() {
+        assert_eq!(
+            equation(292, &[11, 6, 16, 20], &[&Add, &Mul, &Add]),
+            "292 = 11 + 6 * 16 + 20"
+        );
+        assert_eq!(
+            equation(156, &[15, 6], &[&Concat::DECIMAL]),
+            "156 = 15 || 6"
+        );
+    }
+}